@@ -0,0 +1,52 @@
+//! Compares the spatial hash broadphase with checking every pair of colliders.
+//! Run with `cargo bench --bench collision`.
+
+use bevy::ecs::event::Events;
+use bevy::prelude::*;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use rust_game::gameplay::{detect_collisions, detect_collisions_all_pairs, Collider, Collision, CollisionLayer};
+
+// A bullet-hell screen: a quarter of the objects are lasers, the rest asteroids.
+fn populate(world: &mut World, count: usize) {
+    let mut rng = ChaCha8Rng::seed_from_u64(count as u64);
+    for index in 0..count {
+        let position = Vec3::new(rng.random_range(-250.0..250.0), rng.random_range(-550.0..550.0), 0.0);
+        let collider = match index % 4 {
+            0 => Collider::aabb(CollisionLayer::Lazer, Vec2::ONE)
+                .with_mask(&[CollisionLayer::Asteroid, CollisionLayer::Enemy, CollisionLayer::Boss])
+                .swept(),
+            _ => Collider::circle(CollisionLayer::Asteroid, rng.random_range(17.0..41.0)),
+        };
+        world.spawn((collider, Transform::from_translation(position)));
+    }
+}
+
+fn bench_broadphase(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collisions");
+    for count in [100, 400, 1600] {
+        let mut world = World::new();
+        world.init_resource::<Events<Collision>>();
+        populate(&mut world, count);
+
+        let mut spatial_hash = Schedule::default();
+        spatial_hash.add_systems(detect_collisions);
+        let mut all_pairs = Schedule::default();
+        all_pairs.add_systems(detect_collisions_all_pairs);
+
+        group.bench_with_input(BenchmarkId::new("spatial_hash", count), &count, |b, _| b.iter(|| {
+            spatial_hash.run(&mut world);
+            world.resource_mut::<Events<Collision>>().clear();
+        }));
+        group.bench_with_input(BenchmarkId::new("all_pairs", count), &count, |b, _| b.iter(|| {
+            all_pairs.run(&mut world);
+            world.resource_mut::<Events<Collision>>().clear();
+        }));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_broadphase);
+criterion_main!(benches);
@@ -2,6 +2,23 @@ use bevy::prelude::*;
 use bevy::{audio::Volume};
 use core::fmt::Display;
 
+use crate::gameplay::{DamageSound, LazerShootingSound, PowerUpSound};
+
+/// Background music, the sound effects the gameplay plays and the volume settings.
+/// The volumes themselves are loaded with the save data.
+pub struct AudioPlugin;
+
+impl Plugin for AudioPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Startup, (load_audio, setup_background_music))
+            .add_systems(Update, (
+                volume_system::<Music, MusicVolume>.run_if(resource_changed::<MusicVolume>),
+                volume_system::<Sound, SoundVolume>.run_if(resource_changed::<SoundVolume>)
+            ));
+    }
+}
+
 pub trait Volumable {
     fn get_volume(self) -> f32;
 }
@@ -50,4 +67,26 @@ pub fn volume_system<T, R>(
     for mut sink in music_controllers {
         sink.set_volume(Volume::Linear(music_volume.get_volume() / 100.0));
     }
-}
\ No newline at end of file
+}
+fn load_audio(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>
+) {
+    let lazer_shooting_sound = asset_server.load("audio/sfx_laser1.ogg");
+    commands.insert_resource(LazerShootingSound(lazer_shooting_sound));
+
+    let damage_sound = asset_server.load("audio/sfx_lose.ogg");
+    commands.insert_resource(DamageSound(damage_sound));
+
+    let power_up_sound = asset_server.load("audio/sfx_shieldUp.ogg");
+    commands.insert_resource(PowerUpSound(power_up_sound));
+}
+
+fn setup_background_music(
+    asset_server: Res<AssetServer>,
+    music_volume: Res<MusicVolume>,
+    mut commands: Commands
+) {
+    let background_music: Handle<AudioSource> = asset_server.load("audio/639495__romariogrande__space-ambient-voyage.ogg");
+    commands.spawn((AudioPlayer(background_music.clone()), Music, PlaybackSettings {volume: Volume::Linear(music_volume.0 / 100.0), ..PlaybackSettings::LOOP }));
+}
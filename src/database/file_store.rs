@@ -0,0 +1,92 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use bevy::log::info;
+
+use super::format::{self, Decoded};
+use super::{SaveData, SaveError, SaveStore};
+
+const APP_DIRECTORY: &str = "rust-shooter";
+const SAVING_FILE_NAME: &str = "gamedata.data";
+const TEMP_FILE_NAME: &str = "gamedata.data.tmp";
+
+/// Directory for the game's files, e.g. `%APPDATA%\rust-shooter` on Windows
+/// or `~/.local/share/rust-shooter` on Linux. Falls back to the working directory.
+pub fn data_directory() -> PathBuf {
+    match dirs::data_dir() {
+        Some(dir) => dir.join(APP_DIRECTORY),
+        None => PathBuf::from("."),
+    }
+}
+
+/// Stores the save in a file.
+pub struct FileStore {
+    directory: PathBuf,
+    // Older versions of the game kept the save next to the executable's working directory.
+    legacy_path: Option<PathBuf>,
+}
+
+impl FileStore {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        FileStore { directory: directory.into(), legacy_path: None }
+    }
+
+    /// Store in the platform data directory, see [`data_directory`].
+    pub fn in_data_dir() -> Self {
+        FileStore { directory: data_directory(), legacy_path: Some(PathBuf::from(SAVING_FILE_NAME)) }
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.directory.join(SAVING_FILE_NAME)
+    }
+
+    fn read(&self, path: &Path) -> Result<Option<Decoded>, SaveError> {
+        match fs::read(path) {
+            Err(why) if why.kind() == ErrorKind::NotFound => Ok(None),
+            Err(why) => Err(why.into()),
+            Ok(bytes) => Ok(Some(format::decode(&bytes)?)),
+        }
+    }
+
+    fn write(&self, data: &SaveData) -> Result<(), SaveError> {
+        fs::create_dir_all(&self.directory)?;
+        // Write to a temporary file first so a crash mid-write can't leave a half-written save behind.
+        let temp_path = self.directory.join(TEMP_FILE_NAME);
+        fs::write(&temp_path, format::encode(data))?;
+        fs::rename(&temp_path, self.path())?;
+        Ok(())
+    }
+}
+
+impl SaveStore for FileStore {
+    fn load(&self) -> Result<SaveData, SaveError> {
+        let path = self.path();
+        let decoded = match self.read(&path)? {
+            Some(decoded) => decoded,
+            None => match &self.legacy_path {
+                Some(legacy_path) if legacy_path != &path => match self.read(legacy_path)? {
+                    Some(Decoded::Current(data) | Decoded::Legacy(data)) => {
+                        info!("moving save data from {} to {}", legacy_path.display(), path.display());
+                        Decoded::Legacy(data)
+                    }
+                    None => return Ok(SaveData::default()),
+                },
+                _ => return Ok(SaveData::default()),
+            },
+        };
+
+        match decoded {
+            Decoded::Current(data) => Ok(data),
+            Decoded::Legacy(data) => {
+                info!("migrating {} to save schema version {}", path.display(), format::SCHEMA_VERSION);
+                self.write(&data)?;
+                Ok(data)
+            }
+        }
+    }
+
+    fn save(&mut self, data: &SaveData) -> Result<(), SaveError> {
+        self.write(data)
+    }
+}
@@ -0,0 +1,408 @@
+use std::fmt;
+
+use bevy::log::warn;
+
+use super::{LeaderboardEntry, SaveData, DEFAULT_DIFFICULTY};
+
+// Header layout (little-endian):
+// [0..4]   magic
+// [4..6]   schema version
+// [6..8]   reserved, always 0
+// [8..12]  payload length
+// [12..16] CRC-32 of the payload
+pub const MAGIC: [u8; 4] = *b"RSSV";
+pub const SCHEMA_VERSION: u16 = 1;
+const HEADER_SIZE: usize = 16;
+
+// Payload is a list of fields: tag (u16) + length (u16) + value.
+// Readers skip tags they don't know, so new fields can be added without
+// breaking older save files.
+// Record of the default difficulty, kept so older versions of the game still find it.
+const TAG_RECORD: u16 = 1;
+const TAG_SOUND_VOLUME: u16 = 2;
+const TAG_MUSIC_VOLUME: u16 = 3;
+// Repeated once per entry, in leaderboard order.
+const TAG_LEADERBOARD_ENTRY: u16 = 4;
+// Repeated once per difficulty: name length (u8) + name (UTF-8) + record (i32).
+const TAG_DIFFICULTY_RECORD: u16 = 5;
+// Repeated once per action: action name length (u8) + action name (UTF-8),
+// followed by name length (u8) + name (UTF-8) of every bound key.
+const TAG_BINDING: u16 = 6;
+
+// Size of the file written by the first version of the game:
+// record (i32), sound volume (f32) and music volume (f32) in native byte order.
+const LEGACY_SIZE: usize = 12;
+
+#[derive(Debug, PartialEq)]
+pub enum FormatError {
+    Truncated,
+    BadMagic,
+    UnsupportedVersion(u16),
+    ChecksumMismatch { expected: u32, actual: u32 },
+    InvalidField(u16),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Truncated => write!(f, "save data is truncated"),
+            FormatError::BadMagic => write!(f, "save data has unknown magic bytes"),
+            FormatError::UnsupportedVersion(version) => write!(f, "unsupported save schema version {version}"),
+            FormatError::ChecksumMismatch { expected, actual } => {
+                write!(f, "checksum mismatch (expected {expected:#010x}, got {actual:#010x})")
+            }
+            FormatError::InvalidField(tag) => write!(f, "field {tag} has invalid length"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+/// How the save data was read from bytes.
+#[derive(Debug, PartialEq)]
+pub enum Decoded {
+    Current(SaveData),
+    /// Data came from the old raw 12-byte layout and should be rewritten.
+    Legacy(SaveData),
+}
+
+pub fn encode(data: &SaveData) -> Vec<u8> {
+    let mut payload = Vec::new();
+    let default_record = data.records.get(DEFAULT_DIFFICULTY).copied().unwrap_or(0);
+    write_field(&mut payload, TAG_RECORD, &default_record.to_le_bytes());
+    write_field(&mut payload, TAG_SOUND_VOLUME, &data.sound_volume.to_le_bytes());
+    write_field(&mut payload, TAG_MUSIC_VOLUME, &data.music_volume.to_le_bytes());
+    for (difficulty, record) in &data.records {
+        let mut value = Vec::new();
+        write_short_string(&mut value, difficulty);
+        value.extend_from_slice(&record.to_le_bytes());
+        write_field(&mut payload, TAG_DIFFICULTY_RECORD, &value);
+    }
+    for entry in &data.leaderboard {
+        write_field(&mut payload, TAG_LEADERBOARD_ENTRY, &encode_leaderboard_entry(entry));
+    }
+    for (action, keys) in &data.bindings {
+        let mut value = Vec::new();
+        write_short_string(&mut value, action);
+        for key in keys {
+            write_short_string(&mut value, key);
+        }
+        write_field(&mut payload, TAG_BINDING, &value);
+    }
+
+    let mut bytes = Vec::with_capacity(HEADER_SIZE + payload.len());
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&SCHEMA_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&0u16.to_le_bytes());
+    bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+    bytes.extend_from_slice(&payload);
+    bytes
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Decoded, FormatError> {
+    if !bytes.starts_with(&MAGIC) {
+        if bytes.len() <= LEGACY_SIZE && bytes.len().is_multiple_of(4) {
+            return Ok(Decoded::Legacy(decode_legacy(bytes)));
+        }
+        return Err(FormatError::BadMagic);
+    }
+    if bytes.len() < HEADER_SIZE {
+        return Err(FormatError::Truncated);
+    }
+
+    let version = read_u16(&bytes[4..6]);
+    if version == 0 || version > SCHEMA_VERSION {
+        return Err(FormatError::UnsupportedVersion(version));
+    }
+
+    let payload_len = read_u32(&bytes[8..12]) as usize;
+    let payload = bytes.get(HEADER_SIZE..HEADER_SIZE + payload_len).ok_or(FormatError::Truncated)?;
+    let expected = read_u32(&bytes[12..16]);
+    let actual = crc32fast::hash(payload);
+    if expected != actual {
+        return Err(FormatError::ChecksumMismatch { expected, actual });
+    }
+
+    decode_payload(payload).map(Decoded::Current)
+}
+
+fn decode_payload(mut payload: &[u8]) -> Result<SaveData, FormatError> {
+    let mut data = SaveData::default();
+    while !payload.is_empty() {
+        if payload.len() < 4 {
+            return Err(FormatError::Truncated);
+        }
+        let tag = read_u16(&payload[0..2]);
+        let len = read_u16(&payload[2..4]) as usize;
+        let value = payload.get(4..4 + len).ok_or(FormatError::Truncated)?;
+
+        match tag {
+            TAG_RECORD => {
+                let record = i32::from_le_bytes(field_u8x4(tag, value)?);
+                data.records.entry(DEFAULT_DIFFICULTY.to_string()).or_insert(record);
+            }
+            // One unreadable entry of a repeated field shouldn't cost the player the rest of the save.
+            TAG_DIFFICULTY_RECORD => match decode_difficulty_record(value) {
+                Some((difficulty, record)) => {
+                    data.records.insert(difficulty, record);
+                }
+                None => warn!("skipping unreadable difficulty record"),
+            },
+            TAG_SOUND_VOLUME => data.sound_volume = f32::from_le_bytes(field_u8x4(tag, value)?),
+            TAG_MUSIC_VOLUME => data.music_volume = f32::from_le_bytes(field_u8x4(tag, value)?),
+            TAG_LEADERBOARD_ENTRY => match decode_leaderboard_entry(value) {
+                Some(entry) => data.leaderboard.push(entry),
+                None => warn!("skipping unreadable leaderboard entry"),
+            },
+            TAG_BINDING => match decode_binding(value) {
+                Some((action, keys)) => {
+                    data.bindings.insert(action, keys);
+                }
+                None => warn!("skipping unreadable key binding"),
+            },
+            _ => (),
+        }
+        payload = &payload[4 + len..];
+    }
+    Ok(data)
+}
+
+// The legacy file was written field by field, so it may be shorter than 12 bytes
+// if the player never changed the volume. Missing fields keep their defaults.
+fn decode_legacy(bytes: &[u8]) -> SaveData {
+    let mut data = SaveData::default();
+    let mut chunks = bytes.chunks_exact(4).map(|chunk| [chunk[0], chunk[1], chunk[2], chunk[3]]);
+    if let Some(raw) = chunks.next() {
+        data.records.insert(DEFAULT_DIFFICULTY.to_string(), i32::from_ne_bytes(raw));
+    }
+    if let Some(raw) = chunks.next() {
+        data.sound_volume = f32::from_ne_bytes(raw);
+    }
+    if let Some(raw) = chunks.next() {
+        data.music_volume = f32::from_ne_bytes(raw);
+    }
+    data
+}
+
+fn decode_difficulty_record(value: &[u8]) -> Option<(String, i32)> {
+    let (difficulty, rest) = read_short_string(value)?;
+    Some((difficulty, i32::from_le_bytes(rest.try_into().ok()?)))
+}
+
+fn decode_binding(value: &[u8]) -> Option<(String, Vec<String>)> {
+    let (action, mut rest) = read_short_string(value)?;
+    let mut keys = Vec::new();
+    while !rest.is_empty() {
+        let (key, next) = read_short_string(rest)?;
+        keys.push(key);
+        rest = next;
+    }
+    Some((action, keys))
+}
+
+// initials length (u8) + initials (UTF-8) + timestamp (u64) + score (i32)
+// + duration (f32) + asteroids destroyed (u32) + shots fired (u32)
+// + difficulty length (u8) + difficulty (UTF-8).
+// Entries saved before difficulty presets end after the shots fired.
+fn encode_leaderboard_entry(entry: &LeaderboardEntry) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write_short_string(&mut bytes, &entry.initials);
+    bytes.extend_from_slice(&entry.timestamp.to_le_bytes());
+    bytes.extend_from_slice(&entry.score.to_le_bytes());
+    bytes.extend_from_slice(&entry.duration.to_le_bytes());
+    bytes.extend_from_slice(&entry.asteroids_destroyed.to_le_bytes());
+    bytes.extend_from_slice(&entry.shots_fired.to_le_bytes());
+    write_short_string(&mut bytes, &entry.difficulty);
+    bytes
+}
+
+fn decode_leaderboard_entry(value: &[u8]) -> Option<LeaderboardEntry> {
+    let (initials, rest) = read_short_string(value)?;
+    let (stats, rest) = rest.split_at_checked(24)?;
+    let difficulty = match rest.is_empty() {
+        true => DEFAULT_DIFFICULTY.to_string(),
+        false => {
+            let (difficulty, rest) = read_short_string(rest)?;
+            if !rest.is_empty() {
+                return None;
+            }
+            difficulty
+        }
+    };
+
+    Some(LeaderboardEntry {
+        initials,
+        difficulty,
+        timestamp: u64::from_le_bytes(stats[0..8].try_into().ok()?),
+        score: i32::from_le_bytes(stats[8..12].try_into().ok()?),
+        duration: f32::from_le_bytes(stats[12..16].try_into().ok()?),
+        asteroids_destroyed: read_u32(&stats[16..20]),
+        shots_fired: read_u32(&stats[20..24]),
+    })
+}
+
+// Longer strings are cut on a character boundary, so they stay valid UTF-8.
+fn write_short_string(bytes: &mut Vec<u8>, value: &str) {
+    let value = &value[..value.floor_char_boundary(u8::MAX as usize)];
+    bytes.push(value.len() as u8);
+    bytes.extend_from_slice(value.as_bytes());
+}
+
+fn read_short_string(bytes: &[u8]) -> Option<(String, &[u8])> {
+    let (&len, rest) = bytes.split_first()?;
+    let (value, rest) = rest.split_at_checked(len as usize)?;
+    Some((String::from_utf8(value.to_vec()).ok()?, rest))
+}
+
+fn write_field(payload: &mut Vec<u8>, tag: u16, value: &[u8]) {
+    payload.extend_from_slice(&tag.to_le_bytes());
+    payload.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    payload.extend_from_slice(value);
+}
+
+fn field_u8x4(tag: u16, value: &[u8]) -> Result<[u8; 4], FormatError> {
+    value.try_into().map_err(|_| FormatError::InvalidField(tag))
+}
+
+fn read_u16(bytes: &[u8]) -> u16 {
+    u16::from_le_bytes([bytes[0], bytes[1]])
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> SaveData {
+        let mut data = SaveData {
+            sound_volume: 35.0,
+            music_volume: 80.0,
+            ..Default::default()
+        };
+        data.records.insert(DEFAULT_DIFFICULTY.to_string(), 1200);
+        data.records.insert("hard".to_string(), 700);
+        data.leaderboard.push(LeaderboardEntry {
+            initials: "ABC".to_string(),
+            difficulty: "hard".to_string(),
+            timestamp: 1_700_000_000,
+            score: 700,
+            duration: 93.5,
+            asteroids_destroyed: 41,
+            shots_fired: 120,
+        });
+        data.bindings.insert("fire".to_string(), vec!["KeyF".to_string(), "Space".to_string()]);
+        data.bindings.insert("pause".to_string(), Vec::new());
+        data
+    }
+
+    // A current header around a hand-made payload.
+    fn container(payload: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&SCHEMA_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&0u16.to_le_bytes());
+        bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&crc32fast::hash(payload).to_le_bytes());
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    #[test]
+    fn encoded_data_decodes_back() {
+        let data = sample();
+
+        assert_eq!(decode(&encode(&data)), Ok(Decoded::Current(data)));
+    }
+
+    #[test]
+    fn legacy_files_of_every_length_are_migrated() {
+        let mut legacy = Vec::new();
+        legacy.extend_from_slice(&1500i32.to_ne_bytes());
+        legacy.extend_from_slice(&25.0f32.to_ne_bytes());
+        legacy.extend_from_slice(&60.0f32.to_ne_bytes());
+
+        let mut expected = SaveData::default();
+        assert_eq!(decode(&legacy[..0]), Ok(Decoded::Legacy(expected.clone())));
+        expected.records.insert(DEFAULT_DIFFICULTY.to_string(), 1500);
+        assert_eq!(decode(&legacy[..4]), Ok(Decoded::Legacy(expected.clone())));
+        expected.sound_volume = 25.0;
+        assert_eq!(decode(&legacy[..8]), Ok(Decoded::Legacy(expected.clone())));
+        expected.music_volume = 60.0;
+        assert_eq!(decode(&legacy), Ok(Decoded::Legacy(expected)));
+    }
+
+    #[test]
+    fn unknown_magic_is_rejected() {
+        let mut bytes = encode(&sample());
+        bytes[..4].copy_from_slice(b"NOPE");
+
+        assert_eq!(decode(&bytes), Err(FormatError::BadMagic));
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() {
+        for version in [0, SCHEMA_VERSION + 1] {
+            let mut bytes = encode(&sample());
+            bytes[4..6].copy_from_slice(&version.to_le_bytes());
+
+            assert_eq!(decode(&bytes), Err(FormatError::UnsupportedVersion(version)));
+        }
+    }
+
+    #[test]
+    fn corrupted_payload_fails_the_checksum() {
+        let mut bytes = encode(&sample());
+        bytes[HEADER_SIZE + 5] ^= 0xff;
+
+        assert!(matches!(decode(&bytes), Err(FormatError::ChecksumMismatch { .. })));
+    }
+
+    #[test]
+    fn cut_off_data_is_truncated() {
+        let bytes = encode(&sample());
+
+        assert_eq!(decode(&bytes[..HEADER_SIZE - 2]), Err(FormatError::Truncated));
+        assert_eq!(decode(&bytes[..bytes.len() - 1]), Err(FormatError::Truncated));
+    }
+
+    #[test]
+    fn long_strings_are_cut_on_a_character_boundary() {
+        let mut data = SaveData::default();
+        // 254 ASCII bytes, then a character that would straddle the 255 byte limit.
+        let initials = format!("{}é", "a".repeat(254));
+        data.leaderboard.push(LeaderboardEntry { initials, ..sample().leaderboard[0].clone() });
+
+        let Ok(Decoded::Current(decoded)) = decode(&encode(&data)) else {
+            panic!("save data didn't decode");
+        };
+        assert_eq!(decoded.leaderboard[0].initials, "a".repeat(254));
+    }
+
+    #[test]
+    fn unreadable_entries_are_skipped() {
+        let data = sample();
+        let mut payload = Vec::new();
+        write_field(&mut payload, TAG_LEADERBOARD_ENTRY, &[200, b'A']);
+        write_field(&mut payload, TAG_LEADERBOARD_ENTRY, &encode_leaderboard_entry(&data.leaderboard[0]));
+        write_field(&mut payload, TAG_DIFFICULTY_RECORD, &[4, 0xff, 0xfe, 0xfd, 0xfc, 1, 0, 0, 0]);
+        write_field(&mut payload, TAG_BINDING, &[3, b'f']);
+
+        let expected = SaveData { leaderboard: data.leaderboard, ..Default::default() };
+        assert_eq!(decode(&container(&payload)), Ok(Decoded::Current(expected)));
+    }
+
+    #[test]
+    fn unknown_tags_are_skipped() {
+        let mut payload = Vec::new();
+        write_field(&mut payload, 999, b"from a newer version");
+        write_field(&mut payload, TAG_SOUND_VOLUME, &40.0f32.to_le_bytes());
+        write_field(&mut payload, 1000, &[]);
+
+        let expected = SaveData { sound_volume: 40.0, ..Default::default() };
+        assert_eq!(decode(&container(&payload)), Ok(Decoded::Current(expected)));
+    }
+}
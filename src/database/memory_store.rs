@@ -0,0 +1,35 @@
+use super::format::{self, Decoded};
+use super::{SaveData, SaveError, SaveStore};
+
+/// Keeps the encoded save in memory, so nothing touches the disk.
+#[derive(Default, Clone)]
+pub struct MemoryStore {
+    bytes: Option<Vec<u8>>,
+}
+
+impl MemoryStore {
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        MemoryStore { bytes: Some(bytes) }
+    }
+
+    /// The encoded save, if anything was stored.
+    pub fn bytes(&self) -> Option<&[u8]> {
+        self.bytes.as_deref()
+    }
+}
+
+impl SaveStore for MemoryStore {
+    fn load(&self) -> Result<SaveData, SaveError> {
+        match &self.bytes {
+            None => Ok(SaveData::default()),
+            Some(bytes) => match format::decode(bytes)? {
+                Decoded::Current(data) | Decoded::Legacy(data) => Ok(data),
+            },
+        }
+    }
+
+    fn save(&mut self, data: &SaveData) -> Result<(), SaveError> {
+        self.bytes = Some(format::encode(data));
+        Ok(())
+    }
+}
@@ -1,130 +1,137 @@
-use std::fs::File;
-use std::fs::OpenOptions;
-use std::io::prelude::*;
-use std::io::SeekFrom;
-use std::path::Path;
-
-const SAVING_FILE_PATH: &str = "gamedata.data";
-const DEFAULT_RECORD: i32 = 0;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+
+use bevy::log::{error, warn};
+use bevy::prelude::Resource;
+
+mod format;
+mod file_store;
+mod memory_store;
+mod plugin;
+pub use format::FormatError;
+pub use file_store::{data_directory, FileStore};
+pub use memory_store::MemoryStore;
+pub use plugin::PersistencePlugin;
+
+/// Difficulty that records and leaderboard entries from before difficulty presets belong to.
+pub const DEFAULT_DIFFICULTY: &str = "normal";
 const DEFAULT_SOUND_VOLUME: f32 = 100.0;
 const DEFAULT_MUSIC_VOLUME: f32 = 100.0;
 
-pub fn get_record() -> i32 {
-    let path: &Path = Path::new(SAVING_FILE_PATH);
-    if !path.exists() {
-        return DEFAULT_RECORD;
-    }
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveData {
+    /// Best score for each difficulty, by difficulty name.
+    pub records: BTreeMap<String, i32>,
+    pub sound_volume: f32,
+    pub music_volume: f32,
+    pub leaderboard: Vec<LeaderboardEntry>,
+    /// Key names bound to each action, by action name. Actions missing here use their default keys.
+    pub bindings: BTreeMap<String, Vec<String>>,
+}
 
-    let mut file = open_or_create_file(path);
-    match get_u8x4_data(&mut file, 0) {
-        Err(why) => {
-            panic!("couldn't get record: {}", why)
+impl Default for SaveData {
+    fn default() -> Self {
+        SaveData {
+            records: BTreeMap::new(),
+            sound_volume: DEFAULT_SOUND_VOLUME,
+            music_volume: DEFAULT_MUSIC_VOLUME,
+            leaderboard: Vec::new(),
+            bindings: BTreeMap::new(),
         }
-        Ok(volume_bytes) => i32::from_ne_bytes(volume_bytes)
     }
 }
 
-pub fn save_record(record: i32) {
-    let path: &Path = Path::new(SAVING_FILE_PATH);
-    let mut file = open_or_create_file(path);
-    let data = i32::to_ne_bytes(record);
-    match save_u8x4_data(&mut file, 0, data) {
-        Err(why) => panic!("couldn't save record! Why: {}", why),
-        Ok(_) => println!("record is saved: {}({:?})", record, data)
-    };
+/// One finished run in the local leaderboard.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderboardEntry {
+    pub initials: String,
+    pub difficulty: String,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub score: i32,
+    /// Run duration in seconds.
+    pub duration: f32,
+    pub asteroids_destroyed: u32,
+    pub shots_fired: u32,
 }
 
-pub fn get_sound_volume() -> f32 {
-    let path: &Path = Path::new(SAVING_FILE_PATH);
-    if !path.exists() {
-        return DEFAULT_SOUND_VOLUME;
-    }
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Format(FormatError),
+}
 
-    let mut file = open_or_create_file(path);
-    
-    match get_u8x4_data(&mut file, 4) {
-        Err(why) => {
-            panic!("couldn't get sound volume: {}", why)
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(why) => write!(f, "i/o error: {why}"),
+            SaveError::Format(why) => write!(f, "format error: {why}"),
         }
-        Ok(volume_bytes) => f32::from_ne_bytes(volume_bytes)
     }
 }
 
-pub fn save_sound_volume(volume: f32) {
-    let path: &Path = Path::new(SAVING_FILE_PATH);
-    let mut file = open_or_create_file(path);
-    let volume_as_bytes = f32::to_ne_bytes(volume);
-    match save_u8x4_data(&mut file, 4, volume_as_bytes) {
-        Err(why) => panic!("couldn't save sound volume! Why: {}", why),
-        Ok(_) => println!("sound volume is saved: {}({:?})", volume, volume_as_bytes)
-    };
+impl std::error::Error for SaveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SaveError::Io(why) => Some(why),
+            SaveError::Format(why) => Some(why),
+        }
+    }
 }
 
-pub fn get_music_volume() -> f32 {
-    let path: &Path = Path::new(SAVING_FILE_PATH);
-    if !path.exists() {
-        return DEFAULT_MUSIC_VOLUME;
+impl From<io::Error> for SaveError {
+    fn from(why: io::Error) -> Self {
+        SaveError::Io(why)
     }
+}
 
-    let mut file = open_or_create_file(path);  
-    match get_u8x4_data(&mut file, 8) {
-        Err(why) => {
-            panic!("couldn't get music volume: {}", why)
-        }
-        Ok(volume_bytes) => f32::from_ne_bytes(volume_bytes)
+impl From<FormatError> for SaveError {
+    fn from(why: FormatError) -> Self {
+        SaveError::Format(why)
     }
 }
 
-pub fn save_music_volume(volume: f32) {
-    let path: &Path = Path::new(SAVING_FILE_PATH);
-    let mut file = open_or_create_file(path);
-    let volume_as_bytes = f32::to_ne_bytes(volume);
-    match save_u8x4_data(&mut file, 8, volume_as_bytes) {
-        Err(why) => panic!("couldn't save music volume! Why: {}", why),
-        Ok(_) => println!("music volume is saved: {}({:?})", volume, volume_as_bytes)
-    };
+/// Backend that persists `SaveData` somewhere.
+/// A store that has never been saved to returns the default data.
+pub trait SaveStore: Send + Sync {
+    fn load(&self) -> Result<SaveData, SaveError>;
+    fn save(&mut self, data: &SaveData) -> Result<(), SaveError>;
 }
 
-fn open_or_create_file(path: &Path) -> File {
-    let display = path.display();
+/// The save store used by the game.
+/// Failures are logged and never interrupt the game.
+#[derive(Resource)]
+pub struct Storage(Box<dyn SaveStore>);
 
-    let result = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .open(&path);
-
-    match result {
-        Err(why) => panic!("couldn't open {}: {}", display, why),
-        Ok(file) => file,
+impl Storage {
+    pub fn new(store: impl SaveStore + 'static) -> Self {
+        Storage(Box::new(store))
     }
-}
 
-fn get_u8x4_data(mut file: &File, from_start: u64) -> Result<[u8; 4], String> {
-    let mut raw_bytes: [u8; 4] = [0; 4];
-    match file.seek(SeekFrom::Start(from_start)) {
-        Err(why) => {
-            panic!("couldn't seek: {}", why)
-        }
-        Ok(_) => {
-            match file.read(&mut raw_bytes) {
-                Err(why) => Result::Err(format!("couldn't read: {}", why).to_string()),
-                Ok(_) => Result::Ok(raw_bytes),
+    pub fn load(&self) -> SaveData {
+        match self.0.load() {
+            Err(why) => {
+                warn!("couldn't load save data: {}, using defaults", why);
+                SaveData::default()
             }
+            Ok(data) => data,
         }
     }
-}
 
-fn save_u8x4_data(mut file: &File, from_start: u64, data: [u8; 4]) -> Result<usize, String> {
-    match file.seek(SeekFrom::Start(from_start)) {
-        Err(why) => {
-            Result::Err(format!("couldn't seek: {}", why).to_string())
-        }
-        Ok(_) => {
-            match file.write(&data) {
-                Err(why) => Result::Err(format!("couldn't save data! Why: {}", why).to_string()),
-                Ok(value) => Result::Ok(value)
+    /// Changes the saved data. Nothing is saved if the data can't be read, so a save
+    /// that is unreadable for now isn't replaced by the defaults.
+    pub fn update(&mut self, change: impl FnOnce(&mut SaveData)) {
+        let mut data = match self.0.load() {
+            Err(why) => {
+                error!("couldn't load save data: {}, not saving the change", why);
+                return;
             }
+            Ok(data) => data,
+        };
+        change(&mut data);
+        if let Err(why) = self.0.save(&data) {
+            error!("couldn't save data: {}", why);
         }
     }
-}
\ No newline at end of file
+}
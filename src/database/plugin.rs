@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+
+use crate::audio::{MusicVolume, SoundVolume};
+use crate::gameplay::{Bindings, DifficultyPreset, GameplayState, Leaderboard, Score, ScoreRecord};
+use crate::replay::ReplayPlayback;
+use super::Storage;
+
+/// Loads the save data before anything else starts and keeps the score records up to date.
+/// The app inserts the `Storage` to load from.
+pub struct PersistencePlugin;
+
+impl Plugin for PersistencePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(PreStartup, load_save_data)
+            // Played back runs were already scored when they were recorded.
+            .add_systems(OnEnter(GameplayState::GameOver), save_score_record.run_if(not(resource_exists::<ReplayPlayback>)));
+    }
+}
+
+fn load_save_data(
+    storage: Res<Storage>,
+    mut commands: Commands
+) {
+    let save_data = storage.load();
+    let mut record = ScoreRecord::default();
+    for (key, score) in &save_data.records {
+        match DifficultyPreset::from_key(key) {
+            Some(preset) => record.set(preset, *score),
+            None => warn!("ignoring record of unknown difficulty {}", key),
+        }
+    }
+    commands.insert_resource(record);
+    commands.insert_resource(Leaderboard(save_data.leaderboard));
+    commands.insert_resource(SoundVolume(save_data.sound_volume));
+    commands.insert_resource(MusicVolume(save_data.music_volume));
+    commands.insert_resource(Bindings::from_saved(&save_data.bindings));
+}
+
+fn save_score_record(
+    score_res: Res<Score>,
+    preset: Res<DifficultyPreset>,
+    mut record_res: ResMut<ScoreRecord>,
+    mut storage: ResMut<Storage>
+) {
+    let score = score_res.0;
+
+    if score > record_res.get(*preset) {
+        record_res.set(*preset, score);
+        storage.update(|data| {
+            data.records.insert(preset.key().to_string(), score);
+        });
+    }
+}
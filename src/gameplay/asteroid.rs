@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::{
+    Asteroid, Collider, CollisionLayer, DespawnOnExit, DespawnOnRestart, DifficultyLevel, DifficultyPreset, Direction, GameTuning,
+    Health, Interpolated, Rotatable, Speed
+};
+
+const ASTEROID_SPRITE_PATH: &str = "sprites/meteorGrey_big3.png";
+// Fragments fly apart within this angle to each side of the destroyed asteroid's direction.
+const FRAGMENT_SPREAD: f32 = std::f32::consts::FRAC_PI_6;
+
+#[derive(Component, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AsteroidSize {
+    Big,
+    Medium,
+    Small,
+}
+
+impl AsteroidSize {
+    /// Size of the fragments this asteroid breaks into.
+    pub fn smaller(self) -> Option<Self> {
+        match self {
+            AsteroidSize::Big => Some(AsteroidSize::Medium),
+            AsteroidSize::Medium => Some(AsteroidSize::Small),
+            AsteroidSize::Small => None,
+        }
+    }
+}
+
+/// Values of one asteroid size.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct AsteroidSizeTuning {
+    pub diameter: f32,
+    pub move_speed: f32,
+    pub health: i32,
+    pub score: i32,
+    /// Number of smaller asteroids it breaks into.
+    pub fragments: u32,
+}
+
+impl Default for AsteroidSizeTuning {
+    fn default() -> Self {
+        AsteroidSizeTuning { diameter: 82.0, move_speed: 350.0, health: 1, score: 5, fragments: 0 }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct AsteroidSizes {
+    pub big: AsteroidSizeTuning,
+    pub medium: AsteroidSizeTuning,
+    pub small: AsteroidSizeTuning,
+}
+
+impl AsteroidSizes {
+    pub fn get(&self, size: AsteroidSize) -> &AsteroidSizeTuning {
+        match size {
+            AsteroidSize::Big => &self.big,
+            AsteroidSize::Medium => &self.medium,
+            AsteroidSize::Small => &self.small,
+        }
+    }
+}
+
+impl Default for AsteroidSizes {
+    fn default() -> Self {
+        AsteroidSizes {
+            big: AsteroidSizeTuning { diameter: 82.0, move_speed: 350.0, health: 2, score: 5, fragments: 2 },
+            medium: AsteroidSizeTuning { diameter: 56.0, move_speed: 400.0, health: 1, score: 3, fragments: 2 },
+            small: AsteroidSizeTuning { diameter: 34.0, move_speed: 450.0, health: 1, score: 2, fragments: 0 },
+        }
+    }
+}
+
+/// Multiplier of the asteroid speed for the current stage of the run and the difficulty preset.
+pub fn asteroid_speed_multiplier(tuning: &GameTuning, level: DifficultyLevel, preset: DifficultyPreset) -> f32 {
+    level.stage(tuning).speed * tuning.difficulty_presets.get(preset).asteroid_speed
+}
+
+pub fn spawn_asteroid_of_size(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    tuning: &GameTuning,
+    size: AsteroidSize,
+    transform: Transform,
+    direction: Vec2,
+    speed_multiplier: f32
+) {
+    let size_tuning = tuning.asteroid_sizes.get(size);
+    commands.spawn((
+        Sprite {
+            image: asset_server.load(ASTEROID_SPRITE_PATH),
+            custom_size: Some(Vec2::splat(size_tuning.diameter)),
+            ..default()
+        },
+        transform,
+        Speed(size_tuning.move_speed * speed_multiplier),
+        Direction {x: direction.x, y: direction.y},
+        Health(size_tuning.health),
+        Interpolated::default(),
+        Collider::circle(CollisionLayer::Asteroid, size_tuning.diameter / 2.0),
+        DespawnOnRestart,
+        DespawnOnExit,
+        Rotatable,
+        size,
+        Asteroid
+    ));
+}
+
+/// Breaks a destroyed asteroid into smaller ones that fly apart from its direction.
+pub fn spawn_fragments(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    tuning: &GameTuning,
+    size: AsteroidSize,
+    transform: &Transform,
+    direction: &Direction,
+    speed_multiplier: f32
+) {
+    let Some(fragment_size) = size.smaller() else {
+        return;
+    };
+    let count = tuning.asteroid_sizes.get(size).fragments;
+    let direction = Vec2::new(direction.x, direction.y).normalize_or(Vec2::NEG_Y);
+    for index in 0..count {
+        let angle = match count {
+            1 => 0.0,
+            _ => -FRAGMENT_SPREAD + 2.0 * FRAGMENT_SPREAD * index as f32 / (count - 1) as f32,
+        };
+        let fragment_transform = Transform::from_translation(transform.translation).with_rotation(transform.rotation);
+        spawn_asteroid_of_size(
+            commands, asset_server, tuning, fragment_size, fragment_transform,
+            Vec2::from_angle(angle).rotate(direction), speed_multiplier
+        );
+    }
+}
@@ -0,0 +1,263 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::{
+    spawn_enemy_projectile, Collider, ColliderPart, ColliderShape, CollisionLayer, Dead, DespawnOnExit, DespawnOnRestart, Destroy, Direction, GameTuning, Health, Interpolated,
+    Player, Score, Speed, ASTEROID_SPAWN_HEIGHT, PLAYFIELD_HALF_WIDTH
+};
+
+const BOSS_SPRITE_PATH: &str = "sprites/playerShip1_blue.png";
+const BOSS_COLOR: Color = Color::srgb(0.75, 0.45, 1.0);
+const WEAK_POINT_COLOR: Color = Color::srgb(1.0, 0.85, 0.2);
+const BOSS_SIZE: Vec2 = Vec2::new(180.0, 136.0);
+// The boss stops coming down at this height and moves from side to side.
+const BOSS_HEIGHT: f32 = 330.0;
+
+/// Part of the boss a laser can hit, relative to the boss position.
+pub struct BossHitbox {
+    pub offset: Vec2,
+    pub radius: f32,
+    /// Lasers that hit this part deal this many times their damage.
+    pub damage_multiplier: i32,
+}
+
+/// Parts of the boss collider, in order. The weak points come before the hull,
+/// so a laser that touches both hits the weak point.
+pub const BOSS_HITBOXES: [BossHitbox; 3] = [
+    BossHitbox { offset: Vec2::new(-48.0, 48.0), radius: 14.0, damage_multiplier: 3 },
+    BossHitbox { offset: Vec2::new(48.0, 48.0), radius: 14.0, damage_multiplier: 3 },
+    BossHitbox { offset: Vec2::new(0.0, 0.0), radius: 60.0, damage_multiplier: 1 },
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BossPhase {
+    /// Single shots aimed at the player.
+    Aimed,
+    /// Fans of projectiles.
+    Fan,
+    /// A fast sweeping barrage while moving faster.
+    Barrage,
+}
+
+#[derive(Component)]
+pub struct Boss {
+    pub max_health: i32,
+    pub phase: BossPhase,
+    attack_timer: Timer,
+    age: f32,
+}
+
+impl Boss {
+    pub fn phase_for(health: i32, max_health: i32) -> BossPhase {
+        let left = health as f32 / max_health.max(1) as f32;
+        if left > 2.0 / 3.0 {
+            BossPhase::Aimed
+        }
+        else if left > 1.0 / 3.0 {
+            BossPhase::Fan
+        }
+        else {
+            BossPhase::Barrage
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct BossWeakPoint;
+
+/// Score at which the next boss arrives and how many bosses were beaten this run.
+#[derive(Resource)]
+pub struct BossEncounter {
+    pub next_score: i32,
+    pub defeated: usize,
+}
+
+impl BossEncounter {
+    pub fn new(tuning: &GameTuning) -> Self {
+        BossEncounter { next_score: tuning.boss.every_score, defeated: 0 }
+    }
+}
+
+#[derive(Event, Default)]
+pub struct BossDefeatedEvent;
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct BossTuning {
+    /// A boss arrives every time the score grows by this much.
+    pub every_score: i32,
+    pub health: i32,
+    pub move_speed: f32,
+    /// Score given for defeating a boss.
+    pub bonus_score: i32,
+    pub projectile_speed: f32,
+    /// Seconds between two attacks in every phase.
+    pub aimed_interval: f32,
+    pub fan_interval: f32,
+    pub barrage_interval: f32,
+    pub fan_projectiles: u32,
+    /// Angle in degrees the fan covers.
+    pub fan_angle: f32,
+}
+
+impl Default for BossTuning {
+    fn default() -> Self {
+        BossTuning {
+            every_score: 250,
+            health: 40,
+            move_speed: 80.0,
+            bonus_score: 100,
+            projectile_speed: 300.0,
+            aimed_interval: 1.2,
+            fan_interval: 1.6,
+            barrage_interval: 0.3,
+            fan_projectiles: 5,
+            fan_angle: 60.0,
+        }
+    }
+}
+
+impl BossTuning {
+    fn attack_interval(&self, phase: BossPhase) -> f32 {
+        match phase {
+            BossPhase::Aimed => self.aimed_interval,
+            BossPhase::Fan => self.fan_interval,
+            BossPhase::Barrage => self.barrage_interval,
+        }
+    }
+}
+
+pub fn spawn_boss(
+    score: Res<Score>,
+    tuning: Res<GameTuning>,
+    mut encounter: ResMut<BossEncounter>,
+    bosses: Query<(), With<Boss>>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands
+) {
+    if !bosses.is_empty() || score.0 < encounter.next_score {
+        return;
+    }
+    encounter.next_score = score.0 + tuning.boss.every_score;
+    spawn_boss_ship(&mut commands, &asset_server, &tuning, 0.0);
+}
+
+pub fn spawn_boss_ship(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    tuning: &GameTuning,
+    x: f32
+) {
+    commands.spawn((
+        Sprite {
+            image: asset_server.load(BOSS_SPRITE_PATH),
+            color: BOSS_COLOR,
+            custom_size: Some(BOSS_SIZE),
+            flip_y: true,
+            ..default()
+        },
+        Transform::from_xyz(x, ASTEROID_SPAWN_HEIGHT, 0.0),
+        Speed(tuning.boss.move_speed),
+        Direction {x: 0.0, y: -1.0},
+        Health(tuning.boss.health),
+        Interpolated::default(),
+        Collider::compound(CollisionLayer::Boss, BOSS_HITBOXES.iter()
+            .map(|hitbox| ColliderPart { offset: hitbox.offset, shape: ColliderShape::Circle { radius: hitbox.radius } })
+            .collect()),
+        DespawnOnRestart,
+        DespawnOnExit,
+        Boss {
+            max_health: tuning.boss.health,
+            phase: BossPhase::Aimed,
+            attack_timer: Timer::from_seconds(tuning.boss.aimed_interval, TimerMode::Repeating),
+            age: 0.0,
+        }
+    ))
+    .with_children(|parent| {
+        for hitbox in BOSS_HITBOXES.iter().filter(|hitbox| hitbox.damage_multiplier > 1) {
+            parent.spawn((
+                Sprite::from_color(WEAK_POINT_COLOR, Vec2::splat(hitbox.radius * 2.0)),
+                Transform::from_translation(hitbox.offset.extend(0.1)),
+                BossWeakPoint
+            ));
+        }
+    });
+}
+
+pub fn boss_attack(
+    time: Res<Time>,
+    tuning: Res<GameTuning>,
+    player: Option<Single<&Transform, (With<Player>, Without<Boss>)>>,
+    bosses: Query<(&mut Boss, &Health, &Transform, &mut Direction, &mut Speed)>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands
+) {
+    // A level wave can send more than one boss at a time.
+    for (mut boss, health, transform, mut direction, mut speed) in bosses {
+        boss.age += time.delta_secs();
+        let position = transform.translation;
+
+        let phase = Boss::phase_for(health.0, boss.max_health);
+        if phase != boss.phase {
+            boss.phase = phase;
+            let interval = std::time::Duration::from_secs_f32(tuning.boss.attack_interval(phase));
+            boss.attack_timer.set_duration(interval);
+            boss.attack_timer.reset();
+        }
+
+        // Come down, then move from side to side.
+        if direction.y < 0.0 && position.y <= BOSS_HEIGHT {
+            direction.x = 1.0;
+            direction.y = 0.0;
+        }
+        else if direction.y == 0.0 && position.x.abs() > PLAYFIELD_HALF_WIDTH - BOSS_SIZE.x / 2.0 && position.x * direction.x > 0.0 {
+            direction.x = -direction.x;
+        }
+        speed.0 = match phase {
+            BossPhase::Barrage => tuning.boss.move_speed * 1.6,
+            _ => tuning.boss.move_speed,
+        };
+
+        // The boss doesn't attack until it is in place.
+        if direction.y < 0.0 || !boss.attack_timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        let projectile_speed = tuning.boss.projectile_speed;
+        match phase {
+            BossPhase::Aimed => {
+                let target = player.as_ref().map_or(Vec2::new(position.x, -ASTEROID_SPAWN_HEIGHT), |player| player.translation.truncate());
+                let aim = (target - position.truncate()).normalize_or(Vec2::NEG_Y);
+                spawn_enemy_projectile(&mut commands, &asset_server, projectile_speed, position, aim);
+            }
+            BossPhase::Fan => {
+                let count = tuning.boss.fan_projectiles.max(1);
+                let fan = tuning.boss.fan_angle.to_radians();
+                for index in 0..count {
+                    let angle = match count {
+                        1 => 0.0,
+                        _ => -fan / 2.0 + fan * index as f32 / (count - 1) as f32,
+                    };
+                    spawn_enemy_projectile(&mut commands, &asset_server, projectile_speed, position, Vec2::from_angle(angle).rotate(Vec2::NEG_Y));
+                }
+            }
+            BossPhase::Barrage => {
+                let angle = (boss.age * 3.0).sin() * std::f32::consts::FRAC_PI_3;
+                spawn_enemy_projectile(&mut commands, &asset_server, projectile_speed, position, Vec2::from_angle(angle).rotate(Vec2::NEG_Y));
+            }
+        }
+    }
+}
+
+pub fn handle_boss_dead(
+    bosses: Query<Entity, (With<Boss>, With<Dead>)>,
+    mut encounter: ResMut<BossEncounter>,
+    mut defeated_events: EventWriter<BossDefeatedEvent>,
+    mut commands: Commands
+) {
+    for boss in bosses {
+        commands.entity(boss).insert(Destroy);
+        encounter.defeated += 1;
+        defeated_events.write_default();
+    }
+}
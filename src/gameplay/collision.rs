@@ -0,0 +1,333 @@
+use bevy::{math::bounding::{Aabb2d, BoundingCircle, BoundingVolume, IntersectsVolume}, platform::collections::HashMap, prelude::*};
+
+use super::Interpolated;
+
+// Side of a spatial hash cell. About the size of the biggest asteroid,
+// so most objects cover one to four cells.
+const CELL_SIZE: f32 = 128.0;
+
+/// What kind of object a collider belongs to.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CollisionLayer {
+    Player,
+    Lazer,
+    Asteroid,
+    Enemy,
+    EnemyProjectile,
+    Boss,
+    PowerUp,
+}
+
+impl CollisionLayer {
+    pub const fn bit(self) -> u32 {
+        1 << self as u32
+    }
+
+    /// Mask of all the given layers.
+    pub const fn mask(layers: &[CollisionLayer]) -> u32 {
+        let mut mask = 0;
+        let mut index = 0;
+        while index < layers.len() {
+            mask |= layers[index].bit();
+            index += 1;
+        }
+        mask
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColliderShape {
+    Circle { radius: f32 },
+    Aabb { half_size: Vec2 },
+}
+
+/// One shape of a collider, relative to the entity position.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ColliderPart {
+    pub offset: Vec2,
+    pub shape: ColliderShape,
+}
+
+/// Shape of an entity for collision checks. A collider touches the colliders
+/// whose layer is in its `mask`, and the colliders that have its layer in their masks.
+#[derive(Component, Clone, PartialEq, Debug)]
+pub struct Collider {
+    pub layer: CollisionLayer,
+    pub mask: u32,
+    /// Parts are checked in order, a collision reports the first part that touches.
+    pub parts: Vec<ColliderPart>,
+    /// Covers the whole path the entity made this tick, so fast objects
+    /// can't pass through others between two ticks.
+    pub swept: bool,
+}
+
+impl Collider {
+    pub fn circle(layer: CollisionLayer, radius: f32) -> Self {
+        Collider::compound(layer, vec![ColliderPart { offset: Vec2::ZERO, shape: ColliderShape::Circle { radius } }])
+    }
+
+    pub fn aabb(layer: CollisionLayer, size: Vec2) -> Self {
+        Collider::compound(layer, vec![ColliderPart { offset: Vec2::ZERO, shape: ColliderShape::Aabb { half_size: size / 2.0 } }])
+    }
+
+    pub fn compound(layer: CollisionLayer, parts: Vec<ColliderPart>) -> Self {
+        Collider { layer, mask: 0, parts, swept: false }
+    }
+
+    pub fn with_mask(mut self, layers: &[CollisionLayer]) -> Self {
+        self.mask = CollisionLayer::mask(layers);
+        self
+    }
+
+    pub fn swept(mut self) -> Self {
+        self.swept = true;
+        self
+    }
+
+    fn touches(&self, other: &Collider) -> bool {
+        self.mask & other.layer.bit() != 0 || other.mask & self.layer.bit() != 0
+    }
+}
+
+/// One side of a collision.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Contact {
+    pub entity: Entity,
+    /// Index of the collider part that touched.
+    pub part: usize,
+}
+
+/// Two colliders touched this tick.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Collision {
+    pub contacts: [Contact; 2],
+    pub layers: [CollisionLayer; 2],
+}
+
+impl Collision {
+    /// Contacts of a collision between the two layers, in the order the layers are given.
+    pub fn between(&self, first: CollisionLayer, second: CollisionLayer) -> Option<(Contact, Contact)> {
+        let [a, b] = self.contacts;
+        match self.layers {
+            [a_layer, b_layer] if a_layer == first && b_layer == second => Some((a, b)),
+            [a_layer, b_layer] if a_layer == second && b_layer == first => Some((b, a)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Bounds {
+    Circle(BoundingCircle),
+    Aabb(Aabb2d),
+}
+
+impl Bounds {
+    fn of(part: &ColliderPart, position: Vec2) -> Self {
+        let center = position + part.offset;
+        match part.shape {
+            ColliderShape::Circle { radius } => Bounds::Circle(BoundingCircle::new(center, radius)),
+            ColliderShape::Aabb { half_size } => Bounds::Aabb(Aabb2d::new(center, half_size)),
+        }
+    }
+
+    fn aabb(self) -> Aabb2d {
+        match self {
+            Bounds::Circle(circle) => circle.aabb_2d(),
+            Bounds::Aabb(aabb) => aabb,
+        }
+    }
+
+    fn intersects(&self, other: &Bounds) -> bool {
+        match (self, other) {
+            (Bounds::Circle(a), Bounds::Circle(b)) => a.intersects(b),
+            (Bounds::Circle(a), Bounds::Aabb(b)) => a.intersects(b),
+            (Bounds::Aabb(a), Bounds::Circle(b)) => a.intersects(b),
+            (Bounds::Aabb(a), Bounds::Aabb(b)) => a.intersects(b),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Body<'a> {
+    entity: Entity,
+    collider: &'a Collider,
+    position: Vec2,
+    // How far the entity moved this tick.
+    motion: Vec2,
+}
+
+impl Body<'_> {
+    fn new<'a>(entity: Entity, collider: &'a Collider, transform: &Transform, interpolated: Option<&Interpolated>) -> Body<'a> {
+        let position = transform.translation.truncate();
+        let motion = interpolated
+            .and_then(Interpolated::previous_translation)
+            .map_or(Vec2::ZERO, |previous| position - previous.truncate());
+        Body { entity, collider, position, motion }
+    }
+
+    // Bounds of a part relative to the other body. A swept part covers the path
+    // from where it started this tick, moved along with the other body, to where it is now.
+    fn part_bounds(&self, part: &ColliderPart, other: &Body) -> Bounds {
+        let end = Bounds::of(part, self.position);
+        if !self.collider.swept {
+            return end;
+        }
+        let start = Bounds::of(part, self.position - self.motion + other.motion);
+        Bounds::Aabb(start.aabb().merge(&end.aabb()))
+    }
+
+    // Everything the body can touch this tick: its parts where it started and where it is now.
+    fn broad_bounds(&self) -> Aabb2d {
+        let start = self.position - self.motion;
+        self.collider.parts.iter()
+            .flat_map(|part| [Bounds::of(part, start).aabb(), Bounds::of(part, self.position).aabb()])
+            .reduce(|a, b| a.merge(&b))
+            .unwrap_or(Aabb2d::new(self.position, Vec2::ZERO))
+    }
+
+    fn first_contact(&self, other: &Body) -> Option<(usize, usize)> {
+        self.collider.parts.iter().enumerate().find_map(|(index, part)| {
+            let bounds = self.part_bounds(part, other);
+            other.collider.parts.iter()
+                .position(|other_part| bounds.intersects(&other.part_bounds(other_part, self)))
+                .map(|other_index| (index, other_index))
+        })
+    }
+}
+
+fn collide(a: &Body, b: &Body) -> Option<Collision> {
+    if !a.collider.touches(b.collider) {
+        return None;
+    }
+    let (a_part, b_part) = a.first_contact(b)?;
+    Some(Collision {
+        contacts: [Contact { entity: a.entity, part: a_part }, Contact { entity: b.entity, part: b_part }],
+        layers: [a.collider.layer, b.collider.layer],
+    })
+}
+
+/// Uniform grid that finds the colliders close enough to touch.
+#[derive(Default)]
+pub struct SpatialHash {
+    cells: HashMap<IVec2, Vec<usize>>,
+    pairs: Vec<(usize, usize)>,
+}
+
+impl SpatialHash {
+    /// Pairs of indices of the bounds that share a cell, each pair once and in ascending order,
+    /// so the result doesn't depend on the order of the hash map.
+    pub fn pairs(&mut self, bounds: &[Aabb2d]) -> &[(usize, usize)] {
+        // Cells nobody used last time are dropped, the rest keep their allocation.
+        self.cells.retain(|_, cell| {
+            let used = !cell.is_empty();
+            cell.clear();
+            used
+        });
+        self.pairs.clear();
+
+        for (index, aabb) in bounds.iter().enumerate() {
+            let min = (aabb.min / CELL_SIZE).floor().as_ivec2();
+            let max = (aabb.max / CELL_SIZE).floor().as_ivec2();
+            for x in min.x..=max.x {
+                for y in min.y..=max.y {
+                    let cell = self.cells.entry(IVec2::new(x, y)).or_default();
+                    self.pairs.extend(cell.iter().map(|other| (*other, index)));
+                    cell.push(index);
+                }
+            }
+        }
+        self.pairs.sort_unstable();
+        self.pairs.dedup();
+        &self.pairs
+    }
+}
+
+/// Reports the colliders that touch. Only colliders that share a cell of the spatial hash are checked.
+pub fn detect_collisions(
+    colliders: Query<(Entity, &Collider, &Transform, Option<&Interpolated>)>,
+    mut spatial_hash: Local<SpatialHash>,
+    mut collisions: EventWriter<Collision>
+) {
+    let bodies: Vec<Body> = colliders.iter()
+        .map(|(entity, collider, transform, interpolated)| Body::new(entity, collider, transform, interpolated))
+        .collect();
+    let bounds: Vec<Aabb2d> = bodies.iter().map(Body::broad_bounds).collect();
+    for (a, b) in spatial_hash.pairs(&bounds) {
+        if let Some(collision) = collide(&bodies[*a], &bodies[*b]) {
+            collisions.write(collision);
+        }
+    }
+}
+
+/// Checks every pair of colliders, the way collisions were found before the spatial hash.
+/// Reports the same collisions in the same order as `detect_collisions`.
+pub fn detect_collisions_all_pairs(
+    colliders: Query<(Entity, &Collider, &Transform, Option<&Interpolated>)>,
+    mut collisions: EventWriter<Collision>
+) {
+    let bodies: Vec<Body> = colliders.iter()
+        .map(|(entity, collider, transform, interpolated)| Body::new(entity, collider, transform, interpolated))
+        .collect();
+    for (index, a) in bodies.iter().enumerate() {
+        for b in &bodies[index + 1..] {
+            if let Some(collision) = collide(a, b) {
+                collisions.write(collision);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::event::Events;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    fn collisions_of(system: impl IntoScheduleConfigs<bevy::ecs::system::ScheduleSystem, ()>) -> Vec<([Entity; 2], [usize; 2])> {
+        let mut world = World::new();
+        world.init_resource::<Events<Collision>>();
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        for index in 0..300 {
+            let position = Vec3::new(rng.random_range(-250.0..250.0), rng.random_range(-550.0..550.0), 0.0);
+            let collider = match index % 3 {
+                0 => Collider::aabb(CollisionLayer::Lazer, Vec2::new(2.0, 40.0)).with_mask(&[CollisionLayer::Asteroid]),
+                1 => Collider::circle(CollisionLayer::Asteroid, rng.random_range(10.0..200.0)),
+                _ => Collider::compound(CollisionLayer::Player, vec![
+                    ColliderPart { offset: Vec2::new(-30.0, 0.0), shape: ColliderShape::Circle { radius: 10.0 } },
+                    ColliderPart { offset: Vec2::new(30.0, 0.0), shape: ColliderShape::Aabb { half_size: Vec2::splat(15.0) } },
+                ]).with_mask(&[CollisionLayer::Asteroid, CollisionLayer::Player]),
+            };
+            world.spawn((collider, Transform::from_translation(position)));
+        }
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(system);
+        schedule.run(&mut world);
+        world.resource_mut::<Events<Collision>>().drain()
+            .map(|collision| (collision.contacts.map(|contact| contact.entity), collision.contacts.map(|contact| contact.part)))
+            .collect()
+    }
+
+    #[test]
+    fn spatial_hash_finds_the_same_collisions_as_all_pairs() {
+        let expected = collisions_of(detect_collisions_all_pairs.into_configs());
+        assert!(!expected.is_empty());
+        assert_eq!(collisions_of(detect_collisions.into_configs()), expected);
+    }
+
+    #[test]
+    fn collision_reports_contacts_in_the_asked_order() {
+        let [a, b] = [Entity::from_raw(1), Entity::from_raw(2)];
+        let collision = Collision {
+            contacts: [Contact { entity: a, part: 0 }, Contact { entity: b, part: 1 }],
+            layers: [CollisionLayer::Asteroid, CollisionLayer::Lazer],
+        };
+
+        let (lazer, asteroid) = collision.between(CollisionLayer::Lazer, CollisionLayer::Asteroid).unwrap();
+        assert_eq!((lazer.entity, lazer.part, asteroid.entity), (b, 1, a));
+        assert!(collision.between(CollisionLayer::Lazer, CollisionLayer::Enemy).is_none());
+    }
+}
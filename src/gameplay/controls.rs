@@ -0,0 +1,180 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use bevy::prelude::*;
+use bevy::reflect::{DynamicEnum, DynamicVariant, Enum};
+
+/// Number of keys that can be bound to one action at the same time.
+pub const BINDINGS_PER_ACTION: usize = 2;
+/// Stick deflection below this is treated as a stick at rest.
+pub const STICK_DEADZONE: f32 = 0.15;
+
+/// Something the player can do, independent of the key that does it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Fire,
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 4] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Fire,
+        Action::Pause,
+    ];
+
+    /// Stable name used in save data.
+    pub fn key(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Fire => "fire",
+            Action::Pause => "pause",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.key() == key)
+    }
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Gamepad buttons that do the action. Unlike keys, they can't be rebound.
+    pub fn gamepad_buttons(self) -> &'static [GamepadButton] {
+        match self {
+            Action::MoveLeft => &[GamepadButton::DPadLeft],
+            Action::MoveRight => &[GamepadButton::DPadRight],
+            Action::Fire => &[GamepadButton::South, GamepadButton::RightTrigger],
+            Action::Pause => &[GamepadButton::Start],
+        }
+    }
+
+    pub fn pressed_on(self, gamepad: &Gamepad) -> bool {
+        gamepad.any_pressed(self.gamepad_buttons().iter().copied())
+    }
+
+    pub fn just_pressed_on(self, gamepad: &Gamepad) -> bool {
+        gamepad.any_just_pressed(self.gamepad_buttons().iter().copied())
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::MoveLeft => write!(f, "Move left"),
+            Action::MoveRight => write!(f, "Move right"),
+            Action::Fire => write!(f, "Fire"),
+            Action::Pause => write!(f, "Pause"),
+        }
+    }
+}
+
+/// Keys bound to every action. A key is bound to one action at most.
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct Bindings([[Option<KeyCode>; BINDINGS_PER_ACTION]; Action::ALL.len()]);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Bindings([
+            [Some(KeyCode::KeyA), Some(KeyCode::ArrowLeft)],
+            [Some(KeyCode::KeyD), Some(KeyCode::ArrowRight)],
+            [Some(KeyCode::Space), None],
+            [Some(KeyCode::Escape), Some(KeyCode::KeyP)],
+        ])
+    }
+}
+
+impl Bindings {
+    pub fn get(&self, action: Action, slot: usize) -> Option<KeyCode> {
+        self.0[action.index()][slot]
+    }
+
+    pub fn keys(&self, action: Action) -> impl Iterator<Item = KeyCode> + '_ {
+        self.0[action.index()].iter().flatten().copied()
+    }
+
+    pub fn pressed(&self, action: Action, keyboard: &ButtonInput<KeyCode>) -> bool {
+        keyboard.any_pressed(self.keys(action))
+    }
+
+    pub fn just_pressed(&self, action: Action, keyboard: &ButtonInput<KeyCode>) -> bool {
+        keyboard.any_just_pressed(self.keys(action))
+    }
+
+    /// Binds the key to the slot of the action and unbinds it from every other slot.
+    pub fn bind(&mut self, action: Action, slot: usize, key: KeyCode) {
+        for bound in self.0.iter_mut().flatten() {
+            if *bound == Some(key) {
+                *bound = None;
+            }
+        }
+        self.0[action.index()][slot] = Some(key);
+    }
+
+    pub fn clear(&mut self, action: Action, slot: usize) {
+        self.0[action.index()][slot] = None;
+    }
+
+    /// Bindings in the form `database` saves them: key names by action name.
+    pub fn to_saved(&self) -> BTreeMap<String, Vec<String>> {
+        Action::ALL.into_iter()
+            .map(|action| (action.key().to_string(), self.keys(action).map(|key| key.variant_name().to_string()).collect()))
+            .collect()
+    }
+
+    /// Saved bindings on top of the defaults. Actions missing from the save keep their default keys.
+    pub fn from_saved(saved: &BTreeMap<String, Vec<String>>) -> Self {
+        let mut bindings = Bindings::default();
+        for (action_key, names) in saved {
+            let Some(action) = Action::from_key(action_key) else {
+                warn!("ignoring bindings of unknown action {}", action_key);
+                continue;
+            };
+            let mut slots = [None; BINDINGS_PER_ACTION];
+            let keys = names.iter().filter_map(|name| {
+                let key = key_from_name(name);
+                if key.is_none() {
+                    warn!("ignoring binding of unknown key {}", name);
+                }
+                key
+            });
+            for (slot, key) in slots.iter_mut().zip(keys) {
+                *slot = Some(key);
+            }
+            bindings.0[action.index()] = slots;
+        }
+        bindings
+    }
+}
+
+// Keys are saved by the name of their `KeyCode` variant, such as `KeyA` or `ArrowLeft`.
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    KeyCode::from_reflect(&DynamicEnum::new(name, DynamicVariant::Unit))
+}
+
+/// Short name of the key for menus: `A` for `KeyA`, `1` for `Digit1`.
+pub fn key_label(key: KeyCode) -> String {
+    let name = key.variant_name();
+    name.strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+        .unwrap_or(name)
+        .to_string()
+}
+
+/// Movement asked by the gamepad: the D-pad moves at full speed, the left stick
+/// moves as fast as it is pushed.
+pub fn gamepad_movement(gamepad: &Gamepad) -> f32 {
+    if Action::MoveLeft.pressed_on(gamepad) {
+        return -1.0;
+    }
+    if Action::MoveRight.pressed_on(gamepad) {
+        return 1.0;
+    }
+    let stick = gamepad.left_stick().x.clamp(-1.0, 1.0);
+    if stick.abs() < STICK_DEADZONE { 0.0 } else { stick }
+}
@@ -0,0 +1,180 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::{AsteroidSpawTimer, BossEncounter, GameTuning, RunStats, Score};
+
+/// Difficulty chosen in the main menu before a run.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum DifficultyPreset {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Nightmare,
+}
+
+impl DifficultyPreset {
+    pub const ALL: [DifficultyPreset; 4] = [
+        DifficultyPreset::Easy,
+        DifficultyPreset::Normal,
+        DifficultyPreset::Hard,
+        DifficultyPreset::Nightmare,
+    ];
+
+    /// Stable name used in save data and replays.
+    pub fn key(self) -> &'static str {
+        match self {
+            DifficultyPreset::Easy => "easy",
+            DifficultyPreset::Normal => crate::database::DEFAULT_DIFFICULTY,
+            DifficultyPreset::Hard => "hard",
+            DifficultyPreset::Nightmare => "nightmare",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|preset| preset.key() == key)
+    }
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self.index() + 1) % Self::ALL.len()]
+    }
+}
+
+impl std::fmt::Display for DifficultyPreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DifficultyPreset::Easy => "Easy",
+            DifficultyPreset::Normal => "Normal",
+            DifficultyPreset::Hard => "Hard",
+            DifficultyPreset::Nightmare => "Nightmare",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// How a difficulty preset changes the run.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct PresetModifiers {
+    pub player_health: i32,
+    /// Multiplier of the asteroid speed.
+    pub asteroid_speed: f32,
+    /// Multiplier of the asteroid spawn rate.
+    pub spawn_rate: f32,
+    /// Multiplier of the score for every destroyed asteroid.
+    pub score_multiplier: f32,
+}
+
+impl Default for PresetModifiers {
+    fn default() -> Self {
+        PresetModifiers { player_health: 3, asteroid_speed: 1.0, spawn_rate: 1.0, score_multiplier: 1.0 }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct DifficultyPresets {
+    pub easy: PresetModifiers,
+    pub normal: PresetModifiers,
+    pub hard: PresetModifiers,
+    pub nightmare: PresetModifiers,
+}
+
+impl DifficultyPresets {
+    pub fn get(&self, preset: DifficultyPreset) -> &PresetModifiers {
+        match preset {
+            DifficultyPreset::Easy => &self.easy,
+            DifficultyPreset::Normal => &self.normal,
+            DifficultyPreset::Hard => &self.hard,
+            DifficultyPreset::Nightmare => &self.nightmare,
+        }
+    }
+}
+
+impl Default for DifficultyPresets {
+    fn default() -> Self {
+        DifficultyPresets {
+            easy: PresetModifiers { player_health: 5, asteroid_speed: 0.8, spawn_rate: 0.75, score_multiplier: 0.5 },
+            normal: PresetModifiers::default(),
+            hard: PresetModifiers { player_health: 2, asteroid_speed: 1.2, spawn_rate: 1.3, score_multiplier: 1.5 },
+            nightmare: PresetModifiers { player_health: 1, asteroid_speed: 1.4, spawn_rate: 1.7, score_multiplier: 2.5 },
+        }
+    }
+}
+
+/// One step of the difficulty curve. A stage starts once the run lasted `after_seconds`
+/// or the player scored `after_score` points, whichever comes first.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct DifficultyStage {
+    pub after_seconds: f32,
+    pub after_score: i32,
+    /// Multiplier of the asteroid spawn rate.
+    pub spawn_rate: f32,
+    /// Multiplier of the asteroid speed.
+    pub speed: f32,
+    /// Asteroids spawn at most this far from the center of the screen.
+    pub spread: f32,
+}
+
+impl Default for DifficultyStage {
+    fn default() -> Self {
+        DifficultyStage { after_seconds: 0.0, after_score: 0, spawn_rate: 1.0, speed: 1.0, spread: 200.0 }
+    }
+}
+
+pub fn default_difficulty_curve() -> Vec<DifficultyStage> {
+    vec![
+        DifficultyStage { after_seconds: 0.0, after_score: 0, spawn_rate: 1.0, speed: 1.0, spread: 120.0 },
+        DifficultyStage { after_seconds: 30.0, after_score: 50, spawn_rate: 1.2, speed: 1.1, spread: 150.0 },
+        DifficultyStage { after_seconds: 60.0, after_score: 120, spawn_rate: 1.45, speed: 1.2, spread: 180.0 },
+        DifficultyStage { after_seconds: 90.0, after_score: 200, spawn_rate: 1.8, speed: 1.3, spread: 200.0 },
+        DifficultyStage { after_seconds: 120.0, after_score: 300, spawn_rate: 2.5, speed: 1.45, spread: 200.0 },
+    ]
+}
+
+/// Index of the current stage of the difficulty curve.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Debug)]
+pub struct DifficultyLevel(pub usize);
+
+impl DifficultyLevel {
+    pub fn stage(self, tuning: &GameTuning) -> DifficultyStage {
+        tuning.difficulty_curve.get(self.0).cloned().unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for DifficultyLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0 + 1)
+    }
+}
+
+pub fn update_difficulty(
+    stats: Res<RunStats>,
+    score: Res<Score>,
+    tuning: Res<GameTuning>,
+    preset: Res<DifficultyPreset>,
+    bosses: Res<BossEncounter>,
+    mut level: ResMut<DifficultyLevel>,
+    mut spawn_timer: ResMut<AsteroidSpawTimer>
+) {
+    let reached = tuning.difficulty_curve.iter()
+        .rposition(|stage| stats.duration >= stage.after_seconds || score.0 >= stage.after_score)
+        .unwrap_or(0);
+    // Every defeated boss moves the run one stage further.
+    let last = tuning.difficulty_curve.len().saturating_sub(1);
+    level.set_if_neq(DifficultyLevel((reached + bosses.defeated).min(last)));
+
+    let stage = level.stage(&tuning);
+    let spawn_rate = stage.spawn_rate * tuning.difficulty_presets.get(*preset).spawn_rate;
+    let interval = Duration::from_secs_f32(tuning.asteroid_spawn_interval / spawn_rate.max(f32::EPSILON));
+    if spawn_timer.0.duration() != interval {
+        spawn_timer.0.set_duration(interval);
+    }
+}
@@ -0,0 +1,290 @@
+use bevy::{audio::Volume, prelude::*};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::audio::{Sound, SoundVolume};
+use super::{
+    Collider, Collision, CollisionLayer, DamageSound, Dead, DespawnOnExit, DespawnOnRestart, Destroy, Direction, GameRng,
+    GameTuning, Health, Interpolated, Player, PlayerDamageEvent, Speed,
+    ASTEROID_SPAWN_DIAPASON, ASTEROID_SPAWN_HEIGHT, PLAYFIELD_HALF_WIDTH
+};
+
+const ENEMY_SPRITE_PATH: &str = "sprites/playerShip1_blue.png";
+const ENEMY_PROJECTILE_SPRITE_PATH: &str = "sprites/laserBlue03.png";
+const ENEMY_COLOR: Color = Color::srgb(1.0, 0.45, 0.45);
+const ENEMY_SIZE: Vec2 = Vec2::new(66.0, 50.0);
+const ENEMY_RADIUS: f32 = 25.0;
+const ENEMY_PROJECTILE_SIZE: Vec2 = Vec2::new(9.0, 37.0);
+
+// Divers aim at the player once they get down to this height.
+const DIVE_HEIGHT: f32 = 150.0;
+// Strafers fly sideways at this height before leaving.
+const STRAFE_HEIGHT: f32 = 250.0;
+const STRAFE_DURATION: f32 = 4.0;
+// How far sideways a weaving enemy moves for every unit it moves down.
+const WEAVE_WIDTH: f32 = 0.8;
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MovementPattern {
+    /// Weaves left and right on the way down.
+    SineWeave,
+    /// Comes down slowly, then dives at the player.
+    Dive,
+    /// Stops to fly across the screen, then leaves.
+    Strafe,
+}
+
+impl MovementPattern {
+    pub const ALL: [MovementPattern; 3] = [MovementPattern::SineWeave, MovementPattern::Dive, MovementPattern::Strafe];
+}
+
+#[derive(Component)]
+pub struct Enemy {
+    pub pattern: MovementPattern,
+    age: f32,
+    // Age at which the dive or the strafe started.
+    maneuver_started: Option<f32>,
+}
+
+impl Enemy {
+    pub fn new(pattern: MovementPattern) -> Self {
+        Enemy { pattern, age: 0.0, maneuver_started: None }
+    }
+}
+
+#[derive(Component)]
+pub struct EnemyWeapon(pub Timer);
+
+#[derive(Component)]
+pub struct EnemyProjectile;
+
+#[derive(Resource)]
+pub struct EnemySpawnTimer(pub Timer);
+
+#[derive(Event, Default)]
+pub struct EnemyDestroyedEvent;
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct EnemyTuning {
+    /// Seconds between two enemies.
+    pub spawn_interval: f32,
+    pub health: i32,
+    pub move_speed: f32,
+    pub dive_speed: f32,
+    /// Weaves per second of a weaving enemy.
+    pub weave_frequency: f32,
+    /// Seconds between two shots.
+    pub fire_interval: f32,
+    pub projectile_speed: f32,
+    pub projectile_damage: i32,
+    /// Damage to the player when an enemy rams them.
+    pub contact_damage: i32,
+    pub score: i32,
+}
+
+impl Default for EnemyTuning {
+    fn default() -> Self {
+        EnemyTuning {
+            spawn_interval: 7.0,
+            health: 3,
+            move_speed: 120.0,
+            dive_speed: 450.0,
+            weave_frequency: 0.5,
+            fire_interval: 1.6,
+            projectile_speed: 320.0,
+            projectile_damage: 1,
+            contact_damage: 1,
+            score: 15,
+        }
+    }
+}
+
+pub fn spawn_enemy(
+    time: Res<Time>,
+    mut timer: ResMut<EnemySpawnTimer>,
+    mut rng: ResMut<GameRng>,
+    tuning: Res<GameTuning>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands
+) {
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let pattern = MovementPattern::ALL[rng.random_range(0..MovementPattern::ALL.len())];
+    let x = rng.random_range(ASTEROID_SPAWN_DIAPASON.x..=ASTEROID_SPAWN_DIAPASON.y);
+    spawn_enemy_ship(&mut commands, &asset_server, &tuning, x, pattern);
+}
+
+pub fn spawn_enemy_ship(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    tuning: &GameTuning,
+    x: f32,
+    pattern: MovementPattern
+) {
+    commands.spawn((
+        Sprite {
+            image: asset_server.load(ENEMY_SPRITE_PATH),
+            color: ENEMY_COLOR,
+            custom_size: Some(ENEMY_SIZE),
+            flip_y: true,
+            ..default()
+        },
+        Transform::from_xyz(x, ASTEROID_SPAWN_HEIGHT, 0.0),
+        Speed(tuning.enemies.move_speed),
+        Direction {x: 0.0, y: -1.0},
+        Health(tuning.enemies.health),
+        EnemyWeapon(Timer::from_seconds(tuning.enemies.fire_interval, TimerMode::Repeating)),
+        Interpolated::default(),
+        Collider::circle(CollisionLayer::Enemy, ENEMY_RADIUS),
+        DespawnOnRestart,
+        DespawnOnExit,
+        Enemy::new(pattern)
+    ));
+}
+
+pub fn steer_enemies(
+    time: Res<Time>,
+    tuning: Res<GameTuning>,
+    player: Option<Single<&Transform, (With<Player>, Without<Enemy>)>>,
+    enemies: Query<(&mut Enemy, &Transform, &mut Direction, &mut Speed)>
+) {
+    let player_position = player.map(|player| player.translation.truncate());
+    for (mut enemy, transform, mut direction, mut speed) in enemies {
+        enemy.age += time.delta_secs();
+        let position = transform.translation.truncate();
+
+        let new_direction = match enemy.pattern {
+            MovementPattern::SineWeave => {
+                let phase = enemy.age * tuning.enemies.weave_frequency * std::f32::consts::TAU;
+                Vec2::new(phase.cos() * WEAVE_WIDTH, -1.0).normalize()
+            }
+            MovementPattern::Dive => {
+                if enemy.maneuver_started.is_none() && position.y <= DIVE_HEIGHT {
+                    enemy.maneuver_started = Some(enemy.age);
+                    speed.0 = tuning.enemies.dive_speed;
+                    let target = player_position.unwrap_or(Vec2::new(position.x, -ASTEROID_SPAWN_HEIGHT));
+                    (target - position).normalize_or(Vec2::NEG_Y)
+                }
+                else {
+                    Vec2::new(direction.x, direction.y)
+                }
+            }
+            MovementPattern::Strafe => {
+                match enemy.maneuver_started {
+                    None if position.y <= STRAFE_HEIGHT => {
+                        enemy.maneuver_started = Some(enemy.age);
+                        Vec2::new(-position.x.signum(), 0.0)
+                    }
+                    Some(started) if enemy.age - started >= STRAFE_DURATION => Vec2::NEG_Y,
+                    Some(_) if position.x.abs() > PLAYFIELD_HALF_WIDTH - ENEMY_SIZE.x / 2.0 && position.x * direction.x > 0.0 => {
+                        Vec2::new(-direction.x, 0.0)
+                    }
+                    _ => Vec2::new(direction.x, direction.y),
+                }
+            }
+        };
+        direction.x = new_direction.x;
+        direction.y = new_direction.y;
+    }
+}
+
+pub fn enemy_shooting(
+    time: Res<Time>,
+    tuning: Res<GameTuning>,
+    player: Option<Single<&Transform, (With<Player>, Without<Enemy>)>>,
+    enemies: Query<(&Transform, &mut EnemyWeapon), With<Enemy>>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands
+) {
+    let Some(player) = player else {
+        return;
+    };
+    for (transform, mut weapon) in enemies {
+        if !weapon.0.tick(time.delta()).just_finished() {
+            continue;
+        }
+        // Enemies only shoot forward, at a player below them.
+        let aim = player.translation.truncate() - transform.translation.truncate();
+        if aim.y >= 0.0 {
+            continue;
+        }
+
+        spawn_enemy_projectile(&mut commands, &asset_server, tuning.enemies.projectile_speed, transform.translation, aim.normalize());
+    }
+}
+
+/// Spawns a projectile that damages the player, flying in `direction`.
+pub fn spawn_enemy_projectile(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    speed: f32,
+    position: Vec3,
+    direction: Vec2
+) {
+    commands.spawn((
+        Sprite {
+            image: asset_server.load(ENEMY_PROJECTILE_SPRITE_PATH),
+            color: ENEMY_COLOR,
+            custom_size: Some(ENEMY_PROJECTILE_SIZE),
+            ..default()
+        },
+        Transform::from_translation(position.with_z(-1.0))
+            .with_rotation(Quat::from_rotation_arc_2d(Vec2::NEG_Y, direction)),
+        Speed(speed),
+        Direction {x: direction.x, y: direction.y},
+        Interpolated::default(),
+        Collider::circle(CollisionLayer::EnemyProjectile, ENEMY_PROJECTILE_SIZE.x / 2.0),
+        DespawnOnRestart,
+        DespawnOnExit,
+        EnemyProjectile
+    ));
+}
+
+// Enemies and their projectiles damage the player on contact and are removed
+// once they leave the screen.
+pub fn check_enemy_collision(
+    enemies: Query<(Entity, &Transform), Or<(With<Enemy>, With<EnemyProjectile>)>>,
+    mut collisions: EventReader<Collision>,
+    tuning: Res<GameTuning>,
+    sound: Res<DamageSound>,
+    sound_volume: Res<SoundVolume>,
+    mut damage_writer: EventWriter<PlayerDamageEvent>,
+    mut commands: Commands
+) {
+    let mut damage = 0;
+    for collision in collisions.read() {
+        let hit = collision.between(CollisionLayer::Player, CollisionLayer::Enemy)
+            .map(|(_, enemy)| (enemy, tuning.enemies.contact_damage))
+            .or_else(|| collision.between(CollisionLayer::Player, CollisionLayer::EnemyProjectile)
+                .map(|(_, projectile)| (projectile, tuning.enemies.projectile_damage)));
+        if let Some((contact, contact_damage)) = hit {
+            damage += contact_damage;
+            commands.entity(contact.entity).insert(Destroy);
+        }
+    }
+    for (entity, transform) in &enemies {
+        let position = transform.translation;
+        if position.y.abs() > ASTEROID_SPAWN_HEIGHT || position.x.abs() > ASTEROID_SPAWN_HEIGHT {
+            commands.entity(entity).insert(Destroy);
+        }
+    }
+
+    if damage > 0 {
+        damage_writer.write(PlayerDamageEvent { damage });
+        commands.spawn((AudioPlayer(sound.clone()), Sound, PlaybackSettings {volume: Volume::Linear(sound_volume.0 / 100.0), ..PlaybackSettings::DESPAWN }));
+    }
+}
+
+pub fn handle_enemy_dead(
+    enemies: Query<Entity, (With<Enemy>, With<Dead>)>,
+    mut destroyed_events: EventWriter<EnemyDestroyedEvent>,
+    mut commands: Commands
+) {
+    for entity in enemies {
+        commands.entity(entity).insert(Destroy);
+        destroyed_events.write_default();
+    }
+}
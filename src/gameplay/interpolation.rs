@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+
+#[derive(Clone, Copy)]
+struct Pose {
+    translation: Vec3,
+    rotation: Quat,
+}
+
+impl Pose {
+    fn of(transform: &Transform) -> Self {
+        Pose { translation: transform.translation, rotation: transform.rotation }
+    }
+}
+
+/// Smooths the movement of a simulated entity between fixed ticks.
+/// The simulation works with the real `Transform`; between frames it is replaced
+/// by a blend of the last two ticks and restored before the next tick runs.
+#[derive(Component, Default)]
+pub struct Interpolated {
+    previous: Option<Pose>,
+    current: Option<Pose>,
+}
+
+impl Interpolated {
+    /// Translation at the start of the current tick.
+    pub fn previous_translation(&self) -> Option<Vec3> {
+        self.previous.map(|pose| pose.translation)
+    }
+}
+
+pub fn restore_simulated_transforms(
+    transforms: Query<(&mut Transform, &Interpolated)>
+) {
+    for (mut transform, interpolated) in transforms {
+        if let Some(current) = interpolated.current {
+            transform.translation = current.translation;
+            transform.rotation = current.rotation;
+        }
+    }
+}
+
+pub fn store_previous_transforms(
+    transforms: Query<(&Transform, &mut Interpolated)>
+) {
+    for (transform, mut interpolated) in transforms {
+        interpolated.previous = Some(Pose::of(transform));
+    }
+}
+
+pub fn interpolate_transforms(
+    time: Res<Time<Fixed>>,
+    transforms: Query<(&mut Transform, &mut Interpolated)>
+) {
+    let alpha = time.overstep_fraction();
+    for (mut transform, mut interpolated) in transforms {
+        let current = Pose::of(&transform);
+        let previous = interpolated.previous.unwrap_or(current);
+        interpolated.current = Some(current);
+
+        transform.translation = previous.translation.lerp(current.translation, alpha);
+        transform.rotation = previous.rotation.slerp(current.rotation, alpha);
+    }
+}
@@ -0,0 +1,345 @@
+use std::fmt;
+
+use bevy::asset::{io::Reader, AssetLoader, LoadContext, LoadState};
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use super::{
+    asteroid_speed_multiplier, spawn_asteroid_of_size, spawn_boss_ship, spawn_enemy_ship, Asteroid, AsteroidSize,
+    Boss, DifficultyLevel, DifficultyPreset, Enemy, GameRng, GameTuning, GameplayState, MovementPattern,
+    ASTEROID_SPAWN_DIAPASON, ASTEROID_SPAWN_HEIGHT
+};
+
+pub const CAMPAIGN_PATH: &str = "campaign.campaign.ron";
+// Distance between two neighbours of a V formation.
+const VEE_STEP: f32 = 50.0;
+
+/// Endless runs spawn asteroids, enemies and bosses forever; campaign runs play the levels
+/// listed in `assets/campaign.campaign.ron` one after another.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum GameMode {
+    #[default]
+    Endless,
+    Campaign,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 2] = [GameMode::Endless, GameMode::Campaign];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// What a wave is made of.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum WaveSpawn {
+    Asteroid(AsteroidSize),
+    Enemy(MovementPattern),
+    Boss,
+}
+
+/// Where the members of a wave appear along the top of the screen.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Formation {
+    /// Anywhere across the screen.
+    Random,
+    /// All at the same x.
+    Column(f32),
+    /// Evenly across the screen.
+    Row,
+    /// From the center outwards, alternating sides.
+    Vee,
+}
+
+impl Formation {
+    fn x(self, index: u32, count: u32, rng: &mut GameRng) -> f32 {
+        let spread = ASTEROID_SPAWN_DIAPASON.y;
+        match self {
+            Formation::Random => rng.random_range(-spread..=spread),
+            Formation::Column(x) => x.clamp(-spread, spread),
+            Formation::Row if count > 1 => -spread + 2.0 * spread * index as f32 / (count - 1) as f32,
+            Formation::Row => 0.0,
+            Formation::Vee => {
+                let side = if index % 2 == 1 { -1.0 } else { 1.0 };
+                (side * VEE_STEP * index.div_ceil(2) as f32).clamp(-spread, spread)
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Wave {
+    /// Seconds since the start of the level.
+    pub at: f32,
+    pub spawn: WaveSpawn,
+    #[serde(default = "default_formation")]
+    pub formation: Formation,
+    #[serde(default = "default_count")]
+    pub count: u32,
+    /// Seconds between two members of the wave.
+    #[serde(default)]
+    pub interval: f32,
+}
+
+fn default_formation() -> Formation {
+    Formation::Random
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+/// A level of the campaign, loaded from a `.level.ron` file.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone, PartialEq)]
+pub struct Level {
+    pub name: String,
+    pub waves: Vec<Wave>,
+}
+
+struct ScheduledSpawn {
+    time: f32,
+    spawn: WaveSpawn,
+    formation: Formation,
+    index: u32,
+    count: u32,
+}
+
+impl Level {
+    // Every member of every wave, in the order they appear.
+    fn schedule(&self) -> Vec<ScheduledSpawn> {
+        let mut schedule: Vec<ScheduledSpawn> = self.waves.iter()
+            .flat_map(|wave| (0..wave.count).map(move |index| ScheduledSpawn {
+                time: wave.at + wave.interval * index as f32,
+                spawn: wave.spawn,
+                formation: wave.formation,
+                index,
+                count: wave.count,
+            }))
+            .collect();
+        schedule.sort_by(|a, b| a.time.total_cmp(&b.time));
+        schedule
+    }
+}
+
+/// Levels of the campaign in the order they are played.
+#[derive(Asset, TypePath, Debug)]
+pub struct Campaign {
+    pub levels: Vec<Handle<Level>>,
+}
+
+#[derive(Deserialize)]
+struct CampaignFile {
+    levels: Vec<String>,
+}
+
+#[derive(Resource)]
+pub struct CampaignHandle(pub Handle<Campaign>);
+
+#[derive(Debug)]
+pub enum LevelLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl fmt::Display for LevelLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelLoaderError::Io(why) => write!(f, "couldn't read level file: {why}"),
+            LevelLoaderError::Ron(why) => write!(f, "couldn't parse level file: {why}"),
+        }
+    }
+}
+
+impl std::error::Error for LevelLoaderError {}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    type Asset = Level;
+    type Settings = ();
+    type Error = LevelLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await.map_err(LevelLoaderError::Io)?;
+        ron::de::from_bytes(&bytes).map_err(LevelLoaderError::Ron)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+#[derive(Default)]
+pub struct CampaignLoader;
+
+impl AssetLoader for CampaignLoader {
+    type Asset = Campaign;
+    type Settings = ();
+    type Error = LevelLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await.map_err(LevelLoaderError::Io)?;
+        let file: CampaignFile = ron::de::from_bytes(&bytes).map_err(LevelLoaderError::Ron)?;
+        Ok(Campaign { levels: file.levels.into_iter().map(|path| load_context.load(path)).collect() })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["campaign.ron"]
+    }
+}
+
+pub fn load_campaign(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands
+) {
+    commands.insert_resource(CampaignHandle(asset_server.load(CAMPAIGN_PATH)));
+}
+
+/// Plays the levels of the campaign back. Exists only during campaign runs.
+#[derive(Resource, Default)]
+pub struct LevelDirector {
+    /// Index of the current level in the campaign.
+    pub level: usize,
+    pub level_name: Option<String>,
+    /// Every level is cleared.
+    pub finished: bool,
+    /// The campaign or one of its levels couldn't be loaded, so the run ended early.
+    pub load_failed: bool,
+    /// The campaign and every level are loaded. The simulation waits for it, so the first
+    /// wave comes on the same tick however long loading takes, and replays stay in sync.
+    pub loaded: bool,
+    time: f32,
+    schedule: Option<Vec<ScheduledSpawn>>,
+    next_spawn: usize,
+}
+
+/// Whether a campaign run is still waiting for its campaign to load.
+pub fn campaign_loading(
+    director: Option<Res<LevelDirector>>
+) -> bool {
+    director.is_some_and(|director| !director.loaded && !director.finished)
+}
+
+/// Holds the campaign run until the campaign and its levels are loaded, and ends it if
+/// any of them fails to load.
+pub fn wait_for_campaign(
+    campaign_handle: Res<CampaignHandle>,
+    campaigns: Res<Assets<Campaign>>,
+    levels: Res<Assets<Level>>,
+    asset_server: Res<AssetServer>,
+    mut director: ResMut<LevelDirector>,
+    mut gameplay_state: ResMut<NextState<GameplayState>>
+) {
+    let failure = match campaigns.get(&campaign_handle.0) {
+        None => match asset_server.get_load_state(&campaign_handle.0) {
+            Some(LoadState::Failed(why)) => Some(format!("couldn't load the campaign: {why}")),
+            _ => None,
+        },
+        Some(campaign) => {
+            let mut failure = None;
+            let mut loaded = true;
+            for (index, handle) in campaign.levels.iter().enumerate() {
+                if levels.contains(handle) {
+                    continue;
+                }
+                loaded = false;
+                if let Some(LoadState::Failed(why)) = asset_server.get_load_state(handle) {
+                    failure = Some(format!("couldn't load level {}: {why}", index + 1));
+                    break;
+                }
+            }
+            director.loaded = loaded;
+            failure
+        }
+    };
+
+    if let Some(failure) = failure {
+        error!("{}", failure);
+        director.finished = true;
+        director.load_failed = true;
+        gameplay_state.set(GameplayState::GameOver);
+    }
+}
+
+pub fn run_level_director(
+    time: Res<Time>,
+    campaign_handle: Res<CampaignHandle>,
+    campaigns: Res<Assets<Campaign>>,
+    levels: Res<Assets<Level>>,
+    mut director: ResMut<LevelDirector>,
+    hostiles: Query<(), Or<(With<Asteroid>, With<Enemy>, With<Boss>)>>,
+    mut rng: ResMut<GameRng>,
+    tuning: Res<GameTuning>,
+    preset: Res<DifficultyPreset>,
+    difficulty_level: Res<DifficultyLevel>,
+    asset_server: Res<AssetServer>,
+    mut gameplay_state: ResMut<NextState<GameplayState>>,
+    mut commands: Commands
+) {
+    if director.finished {
+        return;
+    }
+    let Some(campaign) = campaigns.get(&campaign_handle.0) else {
+        return;
+    };
+
+    if director.schedule.is_none() {
+        let Some(handle) = campaign.levels.get(director.level) else {
+            director.finished = true;
+            gameplay_state.set(GameplayState::GameOver);
+            return;
+        };
+        let Some(level) = levels.get(handle) else {
+            return;
+        };
+        info!("level {} started: {}", director.level + 1, level.name);
+        director.level_name = Some(level.name.clone());
+        director.schedule = Some(level.schedule());
+        director.time = 0.0;
+        director.next_spawn = 0;
+    }
+
+    let director = &mut *director;
+    let Some(schedule) = &director.schedule else {
+        return;
+    };
+    director.time += time.delta_secs();
+    let first_spawn = director.next_spawn;
+    while let Some(scheduled) = schedule.get(director.next_spawn).filter(|scheduled| scheduled.time <= director.time) {
+        let x = scheduled.formation.x(scheduled.index, scheduled.count, &mut rng);
+        match scheduled.spawn {
+            WaveSpawn::Asteroid(size) => {
+                let speed_multiplier = asteroid_speed_multiplier(&tuning, *difficulty_level, *preset);
+                let transform = Transform::from_xyz(x, ASTEROID_SPAWN_HEIGHT, 0.0)
+                    .with_rotation(Quat::from_rotation_z(rng.random_range(0.0..=360.0)));
+                spawn_asteroid_of_size(&mut commands, &asset_server, &tuning, size, transform, Vec2::NEG_Y, speed_multiplier);
+            }
+            WaveSpawn::Enemy(pattern) => spawn_enemy_ship(&mut commands, &asset_server, &tuning, x, pattern),
+            WaveSpawn::Boss => spawn_boss_ship(&mut commands, &asset_server, &tuning, x),
+        }
+        director.next_spawn += 1;
+    }
+
+    // The level is cleared once every wave has appeared and nothing is left on the screen.
+    // Entities spawned this tick aren't in the query yet.
+    let spawned = director.next_spawn > first_spawn;
+    if !spawned && director.next_spawn >= schedule.len() && hostiles.is_empty() {
+        director.level += 1;
+        director.schedule = None;
+    }
+}
@@ -1,31 +1,52 @@
-use core::fmt;
 use std::f32::consts::TAU;
 
-use bevy::{audio::Volume, math::bounding::{Aabb2d, BoundingCircle, IntersectsVolume}, prelude::*};
+use bevy::{audio::Volume, prelude::*};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use crate::audio::{Sound, SoundVolume};
-use crate::database;
+use crate::database::LeaderboardEntry;
+
+mod asteroid;
+mod boss;
+mod collision;
+mod controls;
+mod difficulty;
+mod enemy;
+mod interpolation;
+mod level;
+mod plugin;
+mod power_up;
+mod tuning;
+pub use asteroid::*;
+pub use boss::*;
+pub use collision::*;
+pub use controls::*;
+pub use difficulty::*;
+pub use enemy::*;
+pub use interpolation::*;
+pub use level::*;
+pub use plugin::*;
+pub use power_up::*;
+pub use tuning::*;
 
 const PLAYER_SPRITE_PATH: &str = "sprites/playerShip1_blue.png";
-const ASTEROID_SPRITE_PATH: &str = "sprites/meteorGrey_big3.png";
 const LAZER_SPRITE_PATH: &str = "sprites/laserBlue03.png";
 
 pub const PLAYER_SPAWN_HEIGHT: f32 = -400.0;
-pub const PLAYER_MOVE_SPEED: f32 = 250.0;
 const PLAYER_BODY_SIZE: Vec2 = Vec2::new(34.0, 75.0);
 const PLAYER_WINGS_SIZE: Vec2 = Vec2::new(99.0, 35.0);
 
-const LAZER_SPEED: f32 = 600.0;
 const LAZER_Y_OFFSET: f32 = 40.0;
+// Lasers hit with the line they travel along.
+const LAZER_COLLIDER_SIZE: Vec2 = Vec2::ONE;
 const LAZER_LAYER: f32 = -1.0;
 
-const ASTEROID_MOVE_SPEED: f32 = 350.0;
 const ASTEROID_SPAWN_HEIGHT: f32 = 550.0;
 const ASTEROID_SPAWN_DIAPASON: Vec2 = Vec2::new(-200.0, 200.0);
-const ASTEROID_DIAMETER: f32 = 82.0;
-const ASTEROID_DAMAGE: i32 = 1;
+const PLAYFIELD_HALF_WIDTH: f32 = 249.5;
 const ASTEROID_ROTATE_SPEED: f32 = 0.25;
 
-const SCORE_BY_ONE_ASTEROID: i32 = 5;
+pub const LEADERBOARD_SIZE: usize = 10;
 
 const FLICK_COLOR: Color = Color::srgb(1.0, 0.0, 0.0);
 
@@ -73,12 +94,14 @@ pub struct Flickerable {
     pub delay: f32,
     pub flick_delay: f32,
     pub current_time: f32,
-    pub is_flicked: bool
+    pub is_flicked: bool,
+    /// Color the sprite gets back between flicks.
+    pub base_color: Color
 }
 
 impl Default for Flickerable {
     fn default() -> Self {
-        Flickerable { flick_number: 3, delay: 0.1, flick_delay: 0.1, current_time: 0.0, is_flicked: true }
+        Flickerable { flick_number: 3, delay: 0.1, flick_delay: 0.1, current_time: 0.0, is_flicked: true, base_color: Color::WHITE }
     }
 }
 
@@ -94,43 +117,166 @@ pub struct LazerShootingSound(pub Handle<AudioSource>);
 #[derive(Resource, Deref)]
 pub struct DamageSound(pub Handle<AudioSource>);
 
-#[derive(Event, Default)]
-pub struct AsteroidCollisionByLazerEvent;
-#[derive(Event, Default)]
-pub struct AsteroidDamageCollisionEvent;
+#[derive(Event)]
+pub struct AsteroidCollisionByLazerEvent {
+    pub size: AsteroidSize,
+}
+/// Something hit the player: an asteroid, an enemy or an enemy projectile.
+#[derive(Event)]
+pub struct PlayerDamageEvent {
+    pub damage: i32,
+}
 #[derive(Event, Default)]
 pub struct GameOverEvent;
 #[derive(Event, Default)]
 pub struct RestartEvent;
 
+/// Source of all gameplay randomness. The same seed always produces the same run.
+#[derive(Resource, Deref, DerefMut)]
+pub struct GameRng {
+    pub seed: u64,
+    #[deref]
+    rng: ChaCha8Rng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng { seed, rng: ChaCha8Rng::seed_from_u64(seed) }
+    }
+}
+
+/// Seed every run should start from, e.g. for a daily challenge.
+/// When `None`, each run gets a random seed.
+#[derive(Resource, Default)]
+pub struct RequestedSeed(pub Option<u64>);
+
+impl RequestedSeed {
+    fn next_rng(&self) -> GameRng {
+        GameRng::new(self.0.unwrap_or_else(rand::random))
+    }
+}
+
+/// Player intent for the next simulation tick, read from the keyboard, a gamepad or a replay.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Debug)]
+pub struct PlayerInput {
+    /// -1.0 is full left, 1.0 is full right.
+    pub movement: f32,
+    pub fire: bool,
+}
+
 #[derive(Resource)]
 pub struct Score(pub i32);
 
-#[derive(Resource)]
-pub struct ScoreRecord(pub i32);
+/// Best score for every difficulty preset.
+#[derive(Resource, Default)]
+pub struct ScoreRecord(pub [i32; DifficultyPreset::ALL.len()]);
+
+impl ScoreRecord {
+    pub fn get(&self, preset: DifficultyPreset) -> i32 {
+        self.0[preset.index()]
+    }
+
+    pub fn set(&mut self, preset: DifficultyPreset, score: i32) {
+        self.0[preset.index()] = score;
+    }
+}
+
+/// Best runs, highest score first.
+#[derive(Resource, Default)]
+pub struct Leaderboard(pub Vec<LeaderboardEntry>);
+
+impl Leaderboard {
+    pub fn qualifies(&self, score: i32) -> bool {
+        score > 0 && (self.0.len() < LEADERBOARD_SIZE || self.0.iter().any(|entry| entry.score < score))
+    }
 
-impl fmt::Display for ScoreRecord {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+    /// Inserts the entry below every run with the same or higher score and returns its place,
+    /// or `None` if it didn't make the cut.
+    pub fn insert(&mut self, entry: LeaderboardEntry) -> Option<usize> {
+        if !self.qualifies(entry.score) {
+            return None;
+        }
+        let place = self.0.iter().position(|other| other.score < entry.score).unwrap_or(self.0.len());
+        self.0.insert(place, entry);
+        self.0.truncate(LEADERBOARD_SIZE);
+        Some(place)
     }
 }
 
+/// Statistics of the current run.
+#[derive(Resource, Default)]
+pub struct RunStats {
+    pub duration: f32,
+    pub asteroids_destroyed: u32,
+    pub shots_fired: u32,
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, States)]
 pub enum GameplayState {
     #[default]
     None,
     Game,
+    /// The run is frozen until the player resumes it.
+    Paused,
     GameOver
 }
 
+/// Whether the simulation should tick. Stops as soon as a state change is requested,
+/// so frames with several ticks can't simulate past the end of a run.
+/// Campaign runs also wait for the campaign to load.
+pub fn simulation_running(
+    state: Res<State<GameplayState>>,
+    next_state: Res<NextState<GameplayState>>,
+    director: Option<Res<LevelDirector>>
+) -> bool {
+    *state.get() == GameplayState::Game
+        && matches!(*next_state, NextState::Unchanged)
+        && director.is_none_or(|director| director.loaded)
+}
+
 pub fn setup(
     mut gameplay_state: ResMut<NextState<GameplayState>>
 ) {
     gameplay_state.set(GameplayState::Game);
 }
 
+/// Pauses the run with the pause key or Start, and resumes it with the same.
+pub fn toggle_pause(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    bindings: Res<Bindings>,
+    state: Res<State<GameplayState>>,
+    mut next_state: ResMut<NextState<GameplayState>>
+) {
+    let pressed = bindings.just_pressed(Action::Pause, &keyboard)
+        || gamepads.iter().any(|gamepad| Action::Pause.just_pressed_on(gamepad));
+    if !pressed {
+        return;
+    }
+    match state.get() {
+        GameplayState::Game => next_state.set(GameplayState::Paused),
+        GameplayState::Paused => next_state.set(GameplayState::Game),
+        _ => (),
+    }
+}
+
+// Timers and animations run on virtual time, so they stop with it.
+pub fn pause_time(
+    mut time: ResMut<Time<Virtual>>
+) {
+    time.pause();
+}
+
+pub fn resume_time(
+    mut time: ResMut<Time<Virtual>>
+) {
+    time.unpause();
+}
+
 pub fn setup_gameplay(
     mut commands: Commands,
+    tuning: Res<GameTuning>,
+    preset: Res<DifficultyPreset>,
     asset_server: Res<AssetServer>
 ) {
     let background_handle = asset_server.load("sprites/darkPurple.png");
@@ -153,9 +299,15 @@ pub fn setup_gameplay(
     commands.spawn((
         Sprite::from_image(asset_server.load(PLAYER_SPRITE_PATH)),
         Transform::from_xyz(0.0, PLAYER_SPAWN_HEIGHT, 0.0),
-        Speed(PLAYER_MOVE_SPEED),
+        Speed(tuning.player_move_speed),
         Direction {x: 0.0, y: 0.0},
-        Health(3),
+        Health(tuning.difficulty_presets.get(*preset).player_health),
+        Interpolated::default(),
+        Collider::compound(CollisionLayer::Player, vec![
+            ColliderPart { offset: Vec2::ZERO, shape: ColliderShape::Aabb { half_size: PLAYER_BODY_SIZE / 2.0 } },
+            ColliderPart { offset: Vec2::ZERO, shape: ColliderShape::Aabb { half_size: PLAYER_WINGS_SIZE / 2.0 } },
+        ])
+        .with_mask(&[CollisionLayer::Asteroid, CollisionLayer::Enemy, CollisionLayer::EnemyProjectile, CollisionLayer::PowerUp]),
         DespawnOnRestart,
         DespawnOnExit,
         Player
@@ -163,11 +315,24 @@ pub fn setup_gameplay(
 }
 
 pub fn insert_resources(
+    requested_seed: Res<RequestedSeed>,
+    tuning: Res<GameTuning>,
+    mode: Res<GameMode>,
     mut commands: Commands
 ) {
-    commands.insert_resource(AsteroidSpawTimer(Timer::from_seconds(2.0, TimerMode::Repeating)));
-    commands.insert_resource(LazerShootingTimer(Timer::from_seconds(0.5, TimerMode::Once)));
+    commands.insert_resource(requested_seed.next_rng());
+    commands.insert_resource(AsteroidSpawTimer(Timer::from_seconds(tuning.asteroid_spawn_interval, TimerMode::Repeating)));
+    commands.insert_resource(LazerShootingTimer(Timer::from_seconds(tuning.lazer_cooldown, TimerMode::Once)));
     commands.insert_resource(Score(0));
+    commands.insert_resource(RunStats::default());
+    commands.insert_resource(PlayerInput::default());
+    commands.insert_resource(DifficultyLevel::default());
+    commands.insert_resource(ActivePowerUps::default());
+    commands.insert_resource(EnemySpawnTimer(Timer::from_seconds(tuning.enemies.spawn_interval, TimerMode::Repeating)));
+    commands.insert_resource(BossEncounter::new(&tuning));
+    if *mode == GameMode::Campaign {
+        commands.insert_resource(LevelDirector::default());
+    }
 }
 
 pub fn remove_resources(
@@ -176,6 +341,14 @@ pub fn remove_resources(
     commands.remove_resource::<AsteroidSpawTimer>();
     commands.remove_resource::<LazerShootingTimer>();
     commands.remove_resource::<Score>();
+    commands.remove_resource::<RunStats>();
+    commands.remove_resource::<GameRng>();
+    commands.remove_resource::<PlayerInput>();
+    commands.remove_resource::<DifficultyLevel>();
+    commands.remove_resource::<ActivePowerUps>();
+    commands.remove_resource::<EnemySpawnTimer>();
+    commands.remove_resource::<BossEncounter>();
+    commands.remove_resource::<LevelDirector>();
 }
 
 pub fn cleanup(
@@ -189,20 +362,40 @@ pub fn cleanup(
     gameplay_state.set(GameplayState::None);
 }
 
+// Runs every frame before the fixed ticks. Fire presses are kept until a tick consumes them.
+pub fn read_player_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    bindings: Res<Bindings>,
+    mut input: ResMut<PlayerInput>
+) {
+    let movement = if bindings.pressed(Action::MoveLeft, &keyboard) {
+        -1.0
+    }
+    else if bindings.pressed(Action::MoveRight, &keyboard) {
+        1.0
+    }
+    else {
+        gamepads.iter().map(gamepad_movement).find(|movement| *movement != 0.0).unwrap_or(0.0)
+    };
+    // Replays store movement in steps of 1/127, so the live run moves the same way as its playback.
+    input.movement = (movement * 127.0).round() / 127.0;
+    input.fire |= bindings.just_pressed(Action::Fire, &keyboard)
+        || gamepads.iter().any(|gamepad| Action::Fire.just_pressed_on(gamepad));
+}
+
+pub fn consume_player_input(
+    mut input: ResMut<PlayerInput>
+) {
+    input.fire = false;
+}
+
 pub fn handle_input(
-    input: Res<ButtonInput<KeyCode>>, 
+    input: Res<PlayerInput>, 
     mut directions: Query<&mut Direction, With<Player>>
 ) {
     for mut dir in &mut directions {
-        if input.pressed(KeyCode::KeyA) { 
-            dir.x = -1.0;
-        }
-        else if input.pressed(KeyCode::KeyD) {
-            dir.x = 1.0; 
-        }
-        else {
-            dir.x = 0.0;
-        }
+        dir.x = input.movement;
     }
 }
 
@@ -219,28 +412,53 @@ pub fn move_objects(
 pub fn lazer_shooting(
     time: Res<Time>, 
     mut timer: ResMut<LazerShootingTimer>, 
-    input: Res<ButtonInput<KeyCode>>, 
+    input: Res<PlayerInput>, 
     player: Query<&Transform, With<Player>>, 
+    tuning: Res<GameTuning>,
+    power_ups: Res<ActivePowerUps>,
+    mut stats: ResMut<RunStats>,
     sound: Res<LazerShootingSound>,
     sound_volume: Res<SoundVolume>,
     asset_server: Res<AssetServer>, 
     mut commands: Commands 
 ) {
+    let cooldown = match power_ups.rapid_fire > 0.0 {
+        true => tuning.lazer_cooldown * tuning.power_ups.rapid_fire_cooldown,
+        false => tuning.lazer_cooldown
+    };
+    let cooldown = std::time::Duration::from_secs_f32(cooldown);
+    if timer.0.duration() != cooldown {
+        timer.0.set_duration(cooldown);
+    }
     if !timer.0.tick(time.delta()).finished() {
         return;
     }
 
-    if input.just_pressed(KeyCode::Space) {
+    if input.fire {
+        let spread = tuning.power_ups.spread_angle.to_radians();
+        let angles: &[f32] = match power_ups.spread_shot > 0.0 {
+            true => &[-spread, 0.0, spread],
+            false => &[0.0]
+        };
         for player_transform in &player {
-            commands.spawn((
-                Sprite::from_image(asset_server.load(LAZER_SPRITE_PATH)),
-                Transform::from_xyz(player_transform.translation.x, player_transform.translation.y + LAZER_Y_OFFSET, LAZER_LAYER),
-                Speed(LAZER_SPEED),
-                Direction {x: 0.0, y: 1.0},
-                DespawnOnRestart,
-                DespawnOnExit,
-                Lazer
-            ));
+            for angle in angles {
+                let direction = Vec2::from_angle(*angle).rotate(Vec2::Y);
+                commands.spawn((
+                    Sprite::from_image(asset_server.load(LAZER_SPRITE_PATH)),
+                    Transform::from_xyz(player_transform.translation.x, player_transform.translation.y + LAZER_Y_OFFSET, LAZER_LAYER)
+                        .with_rotation(Quat::from_rotation_z(*angle)),
+                    Speed(tuning.lazer_speed),
+                    Direction {x: direction.x, y: direction.y},
+                    Interpolated::default(),
+                    Collider::aabb(CollisionLayer::Lazer, LAZER_COLLIDER_SIZE)
+                        .with_mask(&[CollisionLayer::Asteroid, CollisionLayer::Enemy, CollisionLayer::Boss])
+                        .swept(),
+                    DespawnOnRestart,
+                    DespawnOnExit,
+                    Lazer
+                ));
+            }
+            stats.shots_fired += 1;
             commands.spawn((AudioPlayer(sound.clone()), Sound, PlaybackSettings {volume: Volume::Linear(sound_volume.0 / 100.0), ..PlaybackSettings::DESPAWN }));
         }
         timer.0.reset();
@@ -250,103 +468,126 @@ pub fn lazer_shooting(
 pub fn spawn_asteroid(
     time: Res<Time>, 
     mut timer: ResMut<AsteroidSpawTimer>, 
+    mut rng: ResMut<GameRng>,
+    tuning: Res<GameTuning>,
+    preset: Res<DifficultyPreset>,
+    level: Res<DifficultyLevel>,
     mut commands: Commands, 
     asset_server: Res<AssetServer>
 ) {
     if timer.0.tick(time.delta()).just_finished() {
-        let transform = Transform::from_xyz(rand::random_range(ASTEROID_SPAWN_DIAPASON.x..=ASTEROID_SPAWN_DIAPASON.y), ASTEROID_SPAWN_HEIGHT, 0.0);
-        let transform = transform.with_rotation(Quat::from_rotation_z(rand::random_range(0.0..=360.0)));
+        let stage = level.stage(&tuning);
+        let spread = stage.spread.clamp(0.0, ASTEROID_SPAWN_DIAPASON.y);
+        let transform = Transform::from_xyz(rng.random_range(-spread..=spread), ASTEROID_SPAWN_HEIGHT, 0.0);
+        let transform = transform.with_rotation(Quat::from_rotation_z(rng.random_range(0.0..=360.0)));
 
-        commands.spawn((
-            Sprite::from_image(asset_server.load(ASTEROID_SPRITE_PATH)),
-            transform,
-            Speed(ASTEROID_MOVE_SPEED),
-            Direction {x: 0.0, y: -1.0},
-            DespawnOnRestart,
-            DespawnOnExit,
-            Rotatable,
-            Asteroid
-        ));
+        let speed_multiplier = asteroid_speed_multiplier(&tuning, *level, *preset);
+        spawn_asteroid_of_size(&mut commands, &asset_server, &tuning, AsteroidSize::Big, transform, Vec2::NEG_Y, speed_multiplier);
     }
 }
 
 pub fn check_lazer_collision(
-    lazers: Query<(Entity, &Transform), (With<Lazer>, Without<Asteroid>)>, 
-    asteroids: Query<(Entity, &Transform), (With<Asteroid>, Without<Lazer>)>,
-    mut collision_events: EventWriter<AsteroidCollisionByLazerEvent>, 
+    lazers: Query<(Entity, &Transform), With<Lazer>>,
+    mut collisions: EventReader<Collision>,
+    tuning: Res<GameTuning>,
     mut commands: Commands
 ) {
+    for collision in collisions.read() {
+        let hit = collision.between(CollisionLayer::Lazer, CollisionLayer::Asteroid)
+            .or_else(|| collision.between(CollisionLayer::Lazer, CollisionLayer::Enemy))
+            .map(|(lazer, target)| (lazer, target, 1))
+            // A laser that hits a weak point of the boss deals more damage.
+            .or_else(|| collision.between(CollisionLayer::Lazer, CollisionLayer::Boss)
+                .map(|(lazer, boss)| (lazer, boss, BOSS_HITBOXES[boss.part].damage_multiplier)));
+
+        if let Some((lazer, target, damage_multiplier)) = hit {
+            commands.entity(lazer.entity).insert(Destroy);
+            add_damage(&mut commands, target.entity, tuning.lazer_damage * damage_multiplier);
+        }
+    }
+
     for (lazer_entity, lazer) in &lazers {
         if lazer.translation.y > ASTEROID_SPAWN_HEIGHT {
             commands.entity(lazer_entity).insert(Destroy);
-            return;
-        }
-        
-        for (asteroid_entity, astreroid) in &asteroids {
-            let asteroid_collider =  BoundingCircle::new(astreroid.translation.truncate(), ASTEROID_DIAMETER / 2.0);
-            let lazer_collider = Aabb2d::new(lazer.translation.truncate(), lazer.scale.truncate() / 2.0);
-
-            if lazer_collider.intersects(&asteroid_collider) {
-                commands.entity(lazer_entity).insert(Destroy);
-                commands.entity(asteroid_entity).insert(Destroy);
-                collision_events.write_default();
-            }
         }
     }
 }
 
+// Several hits in one tick add up.
+fn add_damage(commands: &mut Commands, target: Entity, damage: i32) {
+    commands.entity(target).entry::<Damage>()
+        .and_modify(move |mut total| total.0 += damage)
+        .or_insert(Damage(damage));
+}
+
+pub fn clamp_player_position(
+    mut player: Single<&mut Transform, With<Player>>
+) {
+    player.translation.x = player.translation.x.clamp(ASTEROID_SPAWN_DIAPASON.x, ASTEROID_SPAWN_DIAPASON.y);
+}
+
 pub fn check_player_collision(
-    mut player: Single<&mut Transform, (With<Player>, Without<Asteroid>)>,
-    asteroids: Query<(Entity, &Transform), (With<Asteroid>, Without<Player>)>,
+    mut collisions: EventReader<Collision>,
+    tuning: Res<GameTuning>,
     sound: Res<DamageSound>,
     sound_volume: Res<SoundVolume>,
-    mut collision_writer: EventWriter<AsteroidDamageCollisionEvent>,
+    mut collision_writer: EventWriter<PlayerDamageEvent>,
     mut commands: Commands
 ) { 
-    let player_transform = &mut*player;
-
-    if player_transform.translation.x < ASTEROID_SPAWN_DIAPASON.x { player_transform.translation.x = ASTEROID_SPAWN_DIAPASON.x }
-    else if player_transform.translation.x > ASTEROID_SPAWN_DIAPASON.y { player_transform.translation.x = ASTEROID_SPAWN_DIAPASON.y }
-
-    let player_center = player_transform.translation.truncate();
-    let body_collider = Aabb2d::new(player_center, PLAYER_BODY_SIZE / 2.0);
-    let wing_collider = Aabb2d::new(player_center, PLAYER_WINGS_SIZE / 2.0);
-
-    for (asteroid_entity, asteroid_transform) in &asteroids {
-        let asteroid_collider = BoundingCircle::new(asteroid_transform.translation.truncate(), ASTEROID_DIAMETER / 2.0);
-        if body_collider.intersects(&asteroid_collider) || wing_collider.intersects(&asteroid_collider) {
-            collision_writer.write_default();
-            commands.entity(asteroid_entity).insert(Destroy);
+    for collision in collisions.read() {
+        if let Some((_, asteroid)) = collision.between(CollisionLayer::Player, CollisionLayer::Asteroid) {
+            collision_writer.write(PlayerDamageEvent { damage: tuning.asteroid_damage });
+            commands.entity(asteroid.entity).insert(Destroy);
             commands.spawn((AudioPlayer(sound.clone()), Sound, PlaybackSettings {volume: Volume::Linear(sound_volume.0 / 100.0), ..PlaybackSettings::DESPAWN }));
         }
     }
 }
 
 pub fn check_botton_wall_collsion(
-    asteroids: Query<(Entity, &Transform), (With<Asteroid>, Without<Player>)>,
+    asteroids: Query<(Entity, &Transform), With<Asteroid>>,
     sound: Res<DamageSound>,
     sound_volume: Res<SoundVolume>,
-    mut collision_events: EventWriter<AsteroidDamageCollisionEvent>, 
+    tuning: Res<GameTuning>,
+    mut collision_events: EventWriter<PlayerDamageEvent>, 
     mut commands: Commands,
 ) {
     for (entity, transform) in &asteroids {
         if transform.translation.y < -ASTEROID_SPAWN_HEIGHT {
-            collision_events.write(AsteroidDamageCollisionEvent);
+            collision_events.write(PlayerDamageEvent { damage: tuning.asteroid_damage });
             commands.entity(entity).insert(Destroy);
             commands.spawn((AudioPlayer(sound.clone()), Sound, PlaybackSettings {volume: Volume::Linear(sound_volume.0 / 100.0), ..PlaybackSettings::DESPAWN }));
         }
     }
 }
 
-pub fn handle_asteroid_damage_collision(
+// Fragments fly sideways, so asteroids bounce off the sides of the playfield.
+pub fn check_side_wall_collision(
+    asteroids: Query<(&Transform, &mut Direction, &AsteroidSize), With<Asteroid>>,
+    tuning: Res<GameTuning>
+) {
+    for (transform, mut direction, size) in asteroids {
+        let edge = PLAYFIELD_HALF_WIDTH - tuning.asteroid_sizes.get(*size).diameter / 2.0;
+        if transform.translation.x.abs() > edge && transform.translation.x * direction.x > 0.0 {
+            direction.x = -direction.x;
+        }
+    }
+}
+
+pub fn handle_player_damage_events(
     player_entity: Single<Entity, With<Player>>,
-    mut reader: EventReader<AsteroidDamageCollisionEvent>,
+    power_ups: Res<ActivePowerUps>,
+    mut reader: EventReader<PlayerDamageEvent>,
     mut commands: Commands
 ) {
+    // The shield absorbs every hit while it lasts.
+    if power_ups.shield > 0.0 {
+        reader.clear();
+        return;
+    }
     if !reader.is_empty() {
         let mut damage = 0;
-        for _ in reader.read() {
-            damage += ASTEROID_DAMAGE;
+        for event in reader.read() {
+            damage += event.damage;
         }
 
         commands.entity(player_entity.entity()).insert(Damage(damage));
@@ -367,11 +608,16 @@ pub fn take_damage(
     }
 }
 
-pub fn handle_player_damage(
-    player: Single<Entity, (With<Player>, With<Damage>, Without<Flickerable>)>,
+// Flashes anything that survives the damage it takes this tick.
+pub fn flick_on_damage(
+    damaged: Query<(Entity, &Health, &Damage, &Sprite), Without<Flickerable>>,
     mut commands: Commands
 ) {
-    commands.entity(player.entity()).insert_if_new(Flickerable::default());
+    for (entity, health, damage, sprite) in damaged {
+        if health.0 > damage.0 {
+            commands.entity(entity).insert(Flickerable { base_color: sprite.color, ..default() });
+        }
+    }
 }
 
 pub fn handle_player_dead(
@@ -383,31 +629,56 @@ pub fn handle_player_dead(
     sprite.color = Color::srgb(1.0, 0.0, 0.0);
 }
 
-pub fn handle_game_over_event(
-    score_res: Res<Score>,
-    mut record_res: ResMut<ScoreRecord>
+pub fn handle_asteroid_dead(
+    asteroids: Query<(Entity, &Transform, &Direction, &AsteroidSize), (With<Asteroid>, With<Dead>)>,
+    mut rng: ResMut<GameRng>,
+    tuning: Res<GameTuning>,
+    preset: Res<DifficultyPreset>,
+    level: Res<DifficultyLevel>,
+    asset_server: Res<AssetServer>,
+    mut collision_events: EventWriter<AsteroidCollisionByLazerEvent>,
+    mut commands: Commands
 ) {
-    let score = score_res.0;
-    let mut record = record_res.0;
-
-    if score > record {
-        record = score;
-        record_res.0 = record;
-        database::save_record(score);
+    let speed_multiplier = asteroid_speed_multiplier(&tuning, *level, *preset);
+    for (entity, transform, direction, size) in asteroids {
+        commands.entity(entity).insert(Destroy);
+        spawn_fragments(&mut commands, &asset_server, &tuning, *size, transform, direction, speed_multiplier);
+        drop_power_up(&mut commands, &mut rng, &tuning, transform.translation);
+        collision_events.write(AsteroidCollisionByLazerEvent { size: *size });
     }
 }
 
 pub fn calculate_score(
     mut score: ResMut<Score>,
-    mut event_reader: EventReader<AsteroidCollisionByLazerEvent>
+    mut stats: ResMut<RunStats>,
+    tuning: Res<GameTuning>,
+    preset: Res<DifficultyPreset>,
+    mut event_reader: EventReader<AsteroidCollisionByLazerEvent>,
+    mut enemy_reader: EventReader<EnemyDestroyedEvent>,
+    mut boss_reader: EventReader<BossDefeatedEvent>
 ) {
+    let score_multiplier = tuning.difficulty_presets.get(*preset).score_multiplier;
     if !event_reader.is_empty() {
-        for _e in event_reader.read() {
-            score.0 += SCORE_BY_ONE_ASTEROID;
+        for event in event_reader.read() {
+            score.0 += (tuning.asteroid_sizes.get(event.size).score as f32 * score_multiplier).round() as i32;
+            stats.asteroids_destroyed += 1;
         }
 
         event_reader.clear();
     }
+    for _ in enemy_reader.read() {
+        score.0 += (tuning.enemies.score as f32 * score_multiplier).round() as i32;
+    }
+    for _ in boss_reader.read() {
+        score.0 += (tuning.boss.bonus_score as f32 * score_multiplier).round() as i32;
+    }
+}
+
+pub fn track_run_time(
+    time: Res<Time>,
+    mut stats: ResMut<RunStats>
+) {
+    stats.duration += time.delta_secs();
 }
 
 pub fn destroy_system(
@@ -422,6 +693,10 @@ pub fn destroy_system(
 pub fn restart_system(
     despawn_entities: Query<Entity, With<DespawnOnRestart>>,
     mut score: ResMut<Score>,
+    mut stats: ResMut<RunStats>,
+    requested_seed: Res<RequestedSeed>,
+    tuning: Res<GameTuning>,
+    mode: Res<GameMode>,
     mut gameplay_state: ResMut<NextState<GameplayState>>,
     mut event_reader: EventReader<RestartEvent>,
     mut commands: Commands
@@ -433,6 +708,18 @@ pub fn restart_system(
             commands.entity(entity).despawn();
         }
         score.0 = 0;
+        *stats = RunStats::default();
+        // Fresh timers, like a first run, so a run recorded after a restart plays back the same.
+        commands.insert_resource(AsteroidSpawTimer(Timer::from_seconds(tuning.asteroid_spawn_interval, TimerMode::Repeating)));
+        commands.insert_resource(LazerShootingTimer(Timer::from_seconds(tuning.lazer_cooldown, TimerMode::Once)));
+        commands.insert_resource(EnemySpawnTimer(Timer::from_seconds(tuning.enemies.spawn_interval, TimerMode::Repeating)));
+        commands.insert_resource(ActivePowerUps::default());
+        commands.insert_resource(BossEncounter::new(&tuning));
+        if *mode == GameMode::Campaign {
+            commands.insert_resource(LevelDirector::default());
+        }
+        commands.insert_resource(requested_seed.next_rng());
+        commands.run_system_cached(setup_gameplay);
         gameplay_state.set(GameplayState::Game);
     }
 }
@@ -470,7 +757,7 @@ pub fn flick_sprites(
         
         sprite.color = match flicker.is_flicked {
             true => FLICK_COLOR,
-            false => Color::WHITE
+            false => flicker.base_color
         };
     }
 }
\ No newline at end of file
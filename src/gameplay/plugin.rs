@@ -0,0 +1,130 @@
+use bevy::app::RunFixedMainLoopSystem;
+use bevy::prelude::*;
+
+use crate::{replay, GameState};
+use super::*;
+
+/// Stages of a simulation tick, in the order they run in `FixedUpdate`.
+/// Every stage only runs while the simulation is running.
+#[derive(SystemSet, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GameplaySet {
+    /// Turns the player input of the tick into movement.
+    Input,
+    /// Spawns, shoots and moves everything.
+    Simulation,
+    /// Finds collisions and reacts to them.
+    Collision,
+    /// Applies damage, removes what was destroyed and counts the score.
+    Damage,
+    /// Visual effects driven by the simulation.
+    Presentation,
+}
+
+/// Everything a run needs: events, assets, states and the fixed-tick simulation.
+/// Doesn't need a window, audio or a GPU, so it also runs under `MinimalPlugins`
+/// with `AssetPlugin` and `StatesPlugin`. The app adds the `GameState` and the
+/// sound handles the gameplay plays.
+pub struct GameplayPlugin;
+
+impl Plugin for GameplayPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<RequestedSeed>()
+            .init_resource::<DifficultyPreset>()
+            .init_resource::<GameMode>()
+            // Replaced by the saved bindings when the save data loads.
+            .init_resource::<Bindings>()
+            // Runs started before the tuning file loads use the default values.
+            .init_resource::<GameTuning>()
+            .init_asset::<GameTuning>()
+            .init_asset_loader::<GameTuningLoader>()
+            .init_asset::<Level>()
+            .init_asset_loader::<LevelLoader>()
+            .init_asset::<Campaign>()
+            .init_asset_loader::<CampaignLoader>()
+            .add_event::<Collision>()
+            .add_event::<AsteroidCollisionByLazerEvent>()
+            .add_event::<PlayerDamageEvent>()
+            .add_event::<EnemyDestroyedEvent>()
+            .add_event::<BossDefeatedEvent>()
+            .add_event::<GameOverEvent>()
+            .add_event::<RestartEvent>()
+            .init_state::<GameplayState>()
+
+            .add_systems(Startup, (load_tuning, load_campaign))
+            .add_systems(Update, apply_tuning_changes.run_if(resource_exists::<GameTuningHandle>))
+
+            .add_systems(OnEnter(GameState::InGame), (insert_resources, setup))
+            // Only a new run spawns the player; resuming a paused run enters `Game` too.
+            .add_systems(OnExit(GameplayState::None), setup_gameplay)
+            .add_systems(Update, toggle_pause.run_if(in_state(GameplayState::Game).or(in_state(GameplayState::Paused))))
+            .add_systems(OnEnter(GameplayState::Paused), pause_time)
+            .add_systems(OnExit(GameplayState::Paused), resume_time)
+            .add_systems(RunFixedMainLoop, (
+                restore_simulated_transforms,
+                read_player_input
+                    .run_if(in_state(GameplayState::Game))
+                    .run_if(not(resource_exists::<replay::ReplayPlayback>)),
+            ).in_set(RunFixedMainLoopSystem::BeforeFixedMainLoop))
+            .add_systems(FixedFirst, store_previous_transforms)
+            .configure_sets(FixedUpdate, (
+                GameplaySet::Input,
+                GameplaySet::Simulation,
+                GameplaySet::Collision,
+                GameplaySet::Damage,
+                GameplaySet::Presentation,
+            ).chain().run_if(simulation_running))
+            .add_systems(FixedUpdate, wait_for_campaign
+                .before(GameplaySet::Input)
+                .run_if(in_state(GameplayState::Game))
+                .run_if(campaign_loading))
+            .add_systems(FixedUpdate, handle_input.in_set(GameplaySet::Input))
+            .add_systems(FixedUpdate, (
+                update_difficulty,
+                tick_power_ups,
+                lazer_shooting,
+                // Campaign runs spawn only what their levels script.
+                run_level_director.run_if(resource_exists::<LevelDirector>),
+                // Regular waves pause while a boss is alive.
+                spawn_asteroid
+                    .run_if(not(any_with_component::<Boss>))
+                    .run_if(not(resource_exists::<LevelDirector>)),
+                spawn_enemy
+                    .run_if(not(any_with_component::<Boss>))
+                    .run_if(not(resource_exists::<LevelDirector>)),
+                spawn_boss.run_if(not(resource_exists::<LevelDirector>)),
+                steer_enemies,
+                enemy_shooting,
+                boss_attack,
+                move_objects,
+                track_run_time,
+            ).chain().in_set(GameplaySet::Simulation))
+            .add_systems(FixedUpdate, (
+                clamp_player_position,
+                detect_collisions,
+                check_lazer_collision,
+                check_player_collision,
+                check_side_wall_collision,
+                check_botton_wall_collsion,
+                check_enemy_collision,
+                check_power_up_collision,
+            ).chain().in_set(GameplaySet::Collision))
+            .add_systems(FixedUpdate, (
+                handle_player_damage_events,
+                flick_on_damage,
+                take_damage,
+                handle_player_dead,
+                handle_asteroid_dead,
+                handle_enemy_dead,
+                handle_boss_dead,
+                destroy_system,
+                calculate_score,
+            ).chain().in_set(GameplaySet::Damage))
+            .add_systems(FixedUpdate, (rotate_around, flick_sprites).in_set(GameplaySet::Presentation))
+            // A fire press lasts until the end of the tick that handles it.
+            .add_systems(FixedUpdate, consume_player_input.after(GameplaySet::Presentation).run_if(simulation_running))
+            .add_systems(RunFixedMainLoop, interpolate_transforms.in_set(RunFixedMainLoopSystem::AfterFixedMainLoop))
+            .add_systems(Update, restart_system.run_if(in_state(GameplayState::GameOver).or(in_state(GameplayState::Paused))))
+            .add_systems(OnExit(GameState::InGame), (cleanup, remove_resources));
+    }
+}
@@ -0,0 +1,166 @@
+use std::fmt;
+
+use bevy::{audio::Volume, prelude::*};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::audio::{Sound, SoundVolume};
+use super::{
+    Collider, Collision, CollisionLayer, DespawnOnExit, DespawnOnRestart, Destroy, Direction, GameRng, GameTuning, Health,
+    Interpolated, Player, Speed, ASTEROID_SPAWN_HEIGHT
+};
+
+const POWER_UP_SIZE: f32 = 30.0;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PowerUp {
+    Shield,
+    RapidFire,
+    SpreadShot,
+    ExtraLife,
+}
+
+impl PowerUp {
+    pub const ALL: [PowerUp; 4] = [PowerUp::Shield, PowerUp::RapidFire, PowerUp::SpreadShot, PowerUp::ExtraLife];
+
+    fn color(self) -> Color {
+        match self {
+            PowerUp::Shield => Color::srgb(0.3, 0.6, 1.0),
+            PowerUp::RapidFire => Color::srgb(1.0, 0.8, 0.2),
+            PowerUp::SpreadShot => Color::srgb(0.8, 0.3, 1.0),
+            PowerUp::ExtraLife => Color::srgb(0.3, 1.0, 0.4),
+        }
+    }
+}
+
+impl fmt::Display for PowerUp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PowerUp::Shield => "Shield",
+            PowerUp::RapidFire => "Rapid fire",
+            PowerUp::SpreadShot => "Spread shot",
+            PowerUp::ExtraLife => "Extra life",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct PowerUpTuning {
+    /// Chance from 0.0 to 1.0 that a destroyed asteroid drops a power-up.
+    pub drop_chance: f64,
+    pub fall_speed: f32,
+    /// Seconds every timed power-up lasts.
+    pub shield_duration: f32,
+    pub rapid_fire_duration: f32,
+    pub spread_shot_duration: f32,
+    /// Multiplier of the shooting cooldown while rapid fire is active.
+    pub rapid_fire_cooldown: f32,
+    /// Angle in degrees between the lasers of a spread shot.
+    pub spread_angle: f32,
+}
+
+impl Default for PowerUpTuning {
+    fn default() -> Self {
+        PowerUpTuning {
+            drop_chance: 0.1,
+            fall_speed: 150.0,
+            shield_duration: 8.0,
+            rapid_fire_duration: 8.0,
+            spread_shot_duration: 8.0,
+            rapid_fire_cooldown: 0.4,
+            spread_angle: 15.0,
+        }
+    }
+}
+
+/// Seconds left of every timed power-up of the player.
+#[derive(Resource, Default, Clone, PartialEq, Debug)]
+pub struct ActivePowerUps {
+    pub shield: f32,
+    pub rapid_fire: f32,
+    pub spread_shot: f32,
+}
+
+impl ActivePowerUps {
+    /// Active power-ups with their remaining time.
+    pub fn remaining(&self) -> impl Iterator<Item = (PowerUp, f32)> {
+        [(PowerUp::Shield, self.shield), (PowerUp::RapidFire, self.rapid_fire), (PowerUp::SpreadShot, self.spread_shot)]
+            .into_iter()
+            .filter(|(_, seconds)| *seconds > 0.0)
+    }
+}
+
+#[derive(Resource, Deref)]
+pub struct PowerUpSound(pub Handle<AudioSource>);
+
+/// Sometimes leaves a falling power-up where an asteroid was destroyed.
+pub fn drop_power_up(
+    commands: &mut Commands,
+    rng: &mut GameRng,
+    tuning: &GameTuning,
+    position: Vec3
+) {
+    if !rng.random_bool(tuning.power_ups.drop_chance.clamp(0.0, 1.0)) {
+        return;
+    }
+    let power_up = PowerUp::ALL[rng.random_range(0..PowerUp::ALL.len())];
+    commands.spawn((
+        Sprite::from_color(power_up.color(), Vec2::splat(POWER_UP_SIZE)),
+        Transform::from_xyz(position.x, position.y, 0.0).with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+        Speed(tuning.power_ups.fall_speed),
+        Direction {x: 0.0, y: -1.0},
+        Interpolated::default(),
+        Collider::circle(CollisionLayer::PowerUp, POWER_UP_SIZE / 2.0),
+        DespawnOnRestart,
+        DespawnOnExit,
+        power_up
+    ));
+}
+
+pub fn check_power_up_collision(
+    mut health: Single<&mut Health, With<Player>>,
+    power_ups: Query<(Entity, &Transform, &PowerUp)>,
+    mut collisions: EventReader<Collision>,
+    tuning: Res<GameTuning>,
+    mut active: ResMut<ActivePowerUps>,
+    sound: Res<PowerUpSound>,
+    sound_volume: Res<SoundVolume>,
+    mut commands: Commands
+) {
+    for (entity, transform, _) in &power_ups {
+        if transform.translation.y < -ASTEROID_SPAWN_HEIGHT {
+            commands.entity(entity).insert(Destroy);
+        }
+    }
+
+    for collision in collisions.read() {
+        let Some((_, contact)) = collision.between(CollisionLayer::Player, CollisionLayer::PowerUp) else {
+            continue;
+        };
+        let Ok((entity, _, power_up)) = power_ups.get(contact.entity) else {
+            continue;
+        };
+
+        match power_up {
+            PowerUp::Shield => active.shield = tuning.power_ups.shield_duration,
+            PowerUp::RapidFire => active.rapid_fire = tuning.power_ups.rapid_fire_duration,
+            PowerUp::SpreadShot => active.spread_shot = tuning.power_ups.spread_shot_duration,
+            PowerUp::ExtraLife => health.0 += 1,
+        }
+        commands.entity(entity).insert(Destroy);
+        commands.spawn((AudioPlayer(sound.clone()), Sound, PlaybackSettings {volume: Volume::Linear(sound_volume.0 / 100.0), ..PlaybackSettings::DESPAWN }));
+    }
+}
+
+pub fn tick_power_ups(
+    time: Res<Time>,
+    mut active: ResMut<ActivePowerUps>
+) {
+    let delta = time.delta_secs();
+    let active = &mut *active;
+    for seconds in [&mut active.shield, &mut active.rapid_fire, &mut active.spread_shot] {
+        *seconds = (*seconds - delta).max(0.0);
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt;
+
+use bevy::asset::{io::Reader, AssetLoader, LoadContext};
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::{default_difficulty_curve, AsteroidSizes, BossTuning, DifficultyPresets, DifficultyStage, EnemyTuning, Player, PowerUpTuning, Speed};
+
+pub const TUNING_PATH: &str = "game.tuning.ron";
+// Bounds of the values that become timer durations, so a typo in the file can't
+// make `Duration` panic.
+const MAX_SECONDS: f32 = 3600.0;
+const MAX_MULTIPLIER: f32 = 1000.0;
+
+/// Gameplay balance values, loaded from `assets/game.tuning.ron`.
+/// Fields missing from the file keep their default values.
+#[derive(Asset, TypePath, Resource, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct GameTuning {
+    pub player_move_speed: f32,
+    pub lazer_speed: f32,
+    /// Seconds between two shots.
+    pub lazer_cooldown: f32,
+    pub lazer_damage: i32,
+    /// Big asteroids spawn at the top and break into medium ones, medium ones into small ones.
+    pub asteroid_sizes: AsteroidSizes,
+    pub asteroid_damage: i32,
+    /// Seconds between two asteroids at the first difficulty stage.
+    pub asteroid_spawn_interval: f32,
+    /// Stages of the run, from the first to the hardest one.
+    pub difficulty_curve: Vec<DifficultyStage>,
+    /// Starting health and multipliers of each difficulty preset.
+    pub difficulty_presets: DifficultyPresets,
+    pub power_ups: PowerUpTuning,
+    pub enemies: EnemyTuning,
+    pub boss: BossTuning,
+}
+
+impl Default for GameTuning {
+    fn default() -> Self {
+        GameTuning {
+            player_move_speed: 250.0,
+            lazer_speed: 600.0,
+            lazer_cooldown: 0.5,
+            lazer_damage: 1,
+            asteroid_sizes: AsteroidSizes::default(),
+            asteroid_damage: 1,
+            asteroid_spawn_interval: 2.0,
+            difficulty_curve: default_difficulty_curve(),
+            difficulty_presets: DifficultyPresets::default(),
+            power_ups: PowerUpTuning::default(),
+            enemies: EnemyTuning::default(),
+            boss: BossTuning::default(),
+        }
+    }
+}
+
+impl GameTuning {
+    /// Checks the values the simulation can't run with: intervals that aren't a sane
+    /// number of seconds and bosses that would arrive on every tick.
+    pub fn validate(&self) -> Result<(), String> {
+        let seconds = [
+            ("lazer_cooldown", self.lazer_cooldown),
+            ("asteroid_spawn_interval", self.asteroid_spawn_interval),
+            ("enemies.spawn_interval", self.enemies.spawn_interval),
+            ("enemies.fire_interval", self.enemies.fire_interval),
+            ("boss.aimed_interval", self.boss.aimed_interval),
+            ("boss.fan_interval", self.boss.fan_interval),
+            ("boss.barrage_interval", self.boss.barrage_interval),
+        ];
+        for (name, value) in seconds {
+            if !(0.0..=MAX_SECONDS).contains(&value) {
+                return Err(format!("{name} must be between 0 and {MAX_SECONDS} seconds, got {value}"));
+            }
+        }
+
+        let presets = &self.difficulty_presets;
+        let multipliers = [
+            ("power_ups.rapid_fire_cooldown", self.power_ups.rapid_fire_cooldown),
+            ("difficulty_presets.easy.spawn_rate", presets.easy.spawn_rate),
+            ("difficulty_presets.normal.spawn_rate", presets.normal.spawn_rate),
+            ("difficulty_presets.hard.spawn_rate", presets.hard.spawn_rate),
+            ("difficulty_presets.nightmare.spawn_rate", presets.nightmare.spawn_rate),
+        ].into_iter().chain(self.difficulty_curve.iter().map(|stage| ("difficulty_curve.spawn_rate", stage.spawn_rate)));
+        for (name, value) in multipliers {
+            if !(0.0..=MAX_MULTIPLIER).contains(&value) {
+                return Err(format!("{name} must be between 0 and {MAX_MULTIPLIER}, got {value}"));
+            }
+        }
+
+        if self.boss.every_score <= 0 {
+            return Err(format!("boss.every_score must be positive, got {}", self.boss.every_score));
+        }
+        Ok(())
+    }
+
+    /// Parses a tuning file and checks its values.
+    pub fn from_ron(bytes: &[u8]) -> Result<Self, GameTuningLoaderError> {
+        let tuning: GameTuning = ron::de::from_bytes(bytes).map_err(GameTuningLoaderError::Ron)?;
+        tuning.validate().map_err(GameTuningLoaderError::Invalid)?;
+        Ok(tuning)
+    }
+}
+
+#[derive(Resource)]
+pub struct GameTuningHandle(pub Handle<GameTuning>);
+
+#[derive(Debug)]
+pub enum GameTuningLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    Invalid(String),
+}
+
+impl fmt::Display for GameTuningLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameTuningLoaderError::Io(why) => write!(f, "couldn't read tuning file: {why}"),
+            GameTuningLoaderError::Ron(why) => write!(f, "couldn't parse tuning file: {why}"),
+            GameTuningLoaderError::Invalid(why) => write!(f, "invalid tuning value: {why}"),
+        }
+    }
+}
+
+impl std::error::Error for GameTuningLoaderError {}
+
+#[derive(Default)]
+pub struct GameTuningLoader;
+
+impl AssetLoader for GameTuningLoader {
+    type Asset = GameTuning;
+    type Settings = ();
+    type Error = GameTuningLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await.map_err(GameTuningLoaderError::Io)?;
+        // A file that fails the checks keeps the tuning loaded before it.
+        GameTuning::from_ron(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tuning.ron"]
+    }
+}
+
+pub fn load_tuning(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands
+) {
+    commands.insert_resource(GameTuningHandle(asset_server.load(TUNING_PATH)));
+}
+
+/// Copies the tuning asset into the `GameTuning` resource whenever it is loaded or edited on disk,
+/// and applies the new values to a run in progress. The asteroid spawn rate follows
+/// through `update_difficulty` and the shooting cooldown through `lazer_shooting`.
+pub fn apply_tuning_changes(
+    mut events: EventReader<AssetEvent<GameTuning>>,
+    handle: Res<GameTuningHandle>,
+    assets: Res<Assets<GameTuning>>,
+    mut tuning: ResMut<GameTuning>,
+    players: Query<&mut Speed, With<Player>>
+) {
+    let changed = events.read().any(|event| match event {
+        AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => *id == handle.0.id(),
+        _ => false,
+    });
+    if !changed {
+        return;
+    }
+    let Some(new_tuning) = assets.get(&handle.0) else {
+        return;
+    };
+
+    info!("game tuning is loaded: {:?}", new_tuning);
+    *tuning = new_tuning.clone();
+    for mut speed in players {
+        speed.0 = tuning.player_move_speed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_tuning_file_is_valid() {
+        let bytes = std::fs::read(format!("assets/{TUNING_PATH}")).unwrap();
+
+        assert!(GameTuning::from_ron(&bytes).is_ok());
+        assert_eq!(GameTuning::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn bad_values_are_rejected() {
+        let bad_files = [
+            "(lazer_cooldown: -0.5)",
+            "(asteroid_spawn_interval: NaN)",
+            "(enemies: (fire_interval: inf))",
+            "(boss: (barrage_interval: -1.0))",
+            "(boss: (every_score: 0))",
+            "(power_ups: (rapid_fire_cooldown: -2.0))",
+            "(difficulty_curve: [(spawn_rate: NaN)])",
+        ];
+        for file in bad_files {
+            let result = GameTuning::from_ron(file.as_bytes());
+            assert!(matches!(result, Err(GameTuningLoaderError::Invalid(_))), "{file} was accepted");
+        }
+    }
+}
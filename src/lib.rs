@@ -0,0 +1,17 @@
+// Bevy systems routinely take many parameters and nested query filters.
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
+use bevy::prelude::*;
+
+pub mod audio;
+pub mod database;
+pub mod gameplay;
+pub mod replay;
+pub mod ui;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, States)]
+pub enum GameState {
+    #[default]
+    MainMenu,
+    InGame,
+}
@@ -0,0 +1,250 @@
+use std::fmt;
+
+use crate::gameplay::{DifficultyPreset, GameMode};
+
+use super::{Replay, ReplayFrame};
+
+// Layout (little-endian):
+// [0..4]   magic
+// [4..6]   format version
+// [6..8]   reserved, always 0
+// [8..16]  seed
+// [16..20] final score
+// [20..24] number of frame runs
+// [24]     difficulty preset
+// [25]     game mode
+// then the frame runs, then CRC-32 of everything before it.
+const MAGIC: [u8; 4] = *b"RSRP";
+// Version 1 stored a frame delta with every frame; since version 2 a frame is one fixed tick.
+// Version 3 added the difficulty preset, version 2 replays are played on Normal.
+// Version 4 added the game mode, older replays are endless runs.
+const FORMAT_VERSION: u16 = 4;
+const NO_MODE_VERSION: u16 = 3;
+const NO_DIFFICULTY_VERSION: u16 = 2;
+const HEADER_SIZE: usize = 26;
+const NO_MODE_HEADER_SIZE: usize = 25;
+const NO_DIFFICULTY_HEADER_SIZE: usize = 24;
+const CHECKSUM_SIZE: usize = 4;
+
+const FLAG_FIRE: u8 = 1;
+// Six hours of the default 64 Hz fixed tick. Longer replays are treated as corrupted
+// rather than allocated.
+const MAX_FRAMES: u64 = 6 * 60 * 60 * 64;
+
+#[derive(Debug, PartialEq)]
+pub enum ReplayFormatError {
+    Truncated,
+    BadMagic,
+    UnsupportedVersion(u16),
+    UnknownDifficulty(u8),
+    UnknownMode(u8),
+    ChecksumMismatch,
+    TooLong,
+}
+
+impl fmt::Display for ReplayFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayFormatError::Truncated => write!(f, "replay is truncated"),
+            ReplayFormatError::BadMagic => write!(f, "file is not a replay"),
+            ReplayFormatError::UnsupportedVersion(version) => write!(f, "unsupported replay version {version}"),
+            ReplayFormatError::UnknownDifficulty(difficulty) => write!(f, "unknown difficulty {difficulty}"),
+            ReplayFormatError::UnknownMode(mode) => write!(f, "unknown game mode {mode}"),
+            ReplayFormatError::ChecksumMismatch => write!(f, "replay checksum mismatch"),
+            ReplayFormatError::TooLong => write!(f, "replay is longer than {MAX_FRAMES} frames"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayFormatError {}
+
+// Consecutive identical frames are stored once as a run:
+// run length (varint) + movement (i8) + flags (u8).
+pub fn encode(replay: &Replay) -> Vec<u8> {
+    let mut runs = Vec::new();
+    let mut run_count: u32 = 0;
+    let mut frames = replay.frames.iter().peekable();
+    while let Some(frame) = frames.next() {
+        let mut length: u64 = 1;
+        while frames.next_if_eq(&frame).is_some() {
+            length += 1;
+        }
+        write_varint(&mut runs, length);
+        runs.push(frame.movement as u8);
+        runs.push(if frame.fire { FLAG_FIRE } else { 0 });
+        run_count += 1;
+    }
+
+    let mut bytes = Vec::with_capacity(HEADER_SIZE + runs.len() + CHECKSUM_SIZE);
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&0u16.to_le_bytes());
+    bytes.extend_from_slice(&replay.seed.to_le_bytes());
+    bytes.extend_from_slice(&replay.final_score.to_le_bytes());
+    bytes.extend_from_slice(&run_count.to_le_bytes());
+    bytes.push(replay.difficulty.index() as u8);
+    bytes.push(replay.mode.index() as u8);
+    bytes.extend_from_slice(&runs);
+    let checksum = crc32fast::hash(&bytes);
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+    bytes
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Replay, ReplayFormatError> {
+    if !bytes.starts_with(&MAGIC) {
+        return Err(ReplayFormatError::BadMagic);
+    }
+    if bytes.len() < NO_DIFFICULTY_HEADER_SIZE + CHECKSUM_SIZE {
+        return Err(ReplayFormatError::Truncated);
+    }
+
+    let (content, checksum) = bytes.split_at(bytes.len() - CHECKSUM_SIZE);
+    if crc32fast::hash(content).to_le_bytes() != checksum {
+        return Err(ReplayFormatError::ChecksumMismatch);
+    }
+
+    let version = u16::from_le_bytes([content[4], content[5]]);
+    let (difficulty, mode, header_size) = match version {
+        FORMAT_VERSION => (read_difficulty(content)?, read_mode(content)?, HEADER_SIZE),
+        NO_MODE_VERSION => (read_difficulty(content)?, GameMode::Endless, NO_MODE_HEADER_SIZE),
+        NO_DIFFICULTY_VERSION => (DifficultyPreset::Normal, GameMode::Endless, NO_DIFFICULTY_HEADER_SIZE),
+        _ => return Err(ReplayFormatError::UnsupportedVersion(version)),
+    };
+
+    let seed = u64::from_le_bytes(content[8..16].try_into().unwrap());
+    let final_score = i32::from_le_bytes(content[16..20].try_into().unwrap());
+    let run_count = u32::from_le_bytes(content[20..24].try_into().unwrap());
+
+    let mut runs = &content[header_size..];
+    let mut frames = Vec::new();
+    let mut frame_count: u64 = 0;
+    for _ in 0..run_count {
+        let length = read_varint(&mut runs)?;
+        frame_count = frame_count.saturating_add(length);
+        if frame_count > MAX_FRAMES {
+            return Err(ReplayFormatError::TooLong);
+        }
+        let [movement, flags] = *runs.first_chunk::<2>().ok_or(ReplayFormatError::Truncated)?;
+        runs = &runs[2..];
+
+        let frame = ReplayFrame { movement: movement as i8, fire: flags & FLAG_FIRE != 0 };
+        frames.extend(std::iter::repeat_n(frame, length as usize));
+    }
+
+    Ok(Replay { seed, difficulty, mode, final_score, frames })
+}
+
+fn read_difficulty(content: &[u8]) -> Result<DifficultyPreset, ReplayFormatError> {
+    let index = *content.get(NO_DIFFICULTY_HEADER_SIZE).ok_or(ReplayFormatError::Truncated)?;
+    DifficultyPreset::ALL.get(index as usize).copied().ok_or(ReplayFormatError::UnknownDifficulty(index))
+}
+
+fn read_mode(content: &[u8]) -> Result<GameMode, ReplayFormatError> {
+    let index = *content.get(NO_MODE_HEADER_SIZE).ok_or(ReplayFormatError::Truncated)?;
+    GameMode::ALL.get(index as usize).copied().ok_or(ReplayFormatError::UnknownMode(index))
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, ReplayFormatError> {
+    let mut value: u64 = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first().ok_or(ReplayFormatError::Truncated)?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(ReplayFormatError::Truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Replay {
+        let mut frames = vec![ReplayFrame { movement: 0, fire: false }; 300];
+        frames.extend([ReplayFrame { movement: -127, fire: true }; 3]);
+        frames.push(ReplayFrame { movement: 64, fire: false });
+        Replay { seed: 0xdead_beef, difficulty: DifficultyPreset::Hard, mode: GameMode::Campaign, final_score: 4200, frames }
+    }
+
+    // Bytes of `sample` written as an older version, without the header bytes it didn't have.
+    fn sample_as_version(version: u16, header_size: usize) -> Vec<u8> {
+        let encoded = encode(&sample());
+        let mut bytes = encoded[..header_size].to_vec();
+        bytes[4..6].copy_from_slice(&version.to_le_bytes());
+        bytes.extend_from_slice(&encoded[HEADER_SIZE..encoded.len() - CHECKSUM_SIZE]);
+        let checksum = crc32fast::hash(&bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn encoded_replay_decodes_back() {
+        let replay = sample();
+
+        assert_eq!(decode(&encode(&replay)), Ok(replay));
+    }
+
+    #[test]
+    fn older_versions_default_the_missing_fields() {
+        let version_3 = decode(&sample_as_version(NO_MODE_VERSION, NO_MODE_HEADER_SIZE)).unwrap();
+        assert_eq!((version_3.difficulty, version_3.mode), (DifficultyPreset::Hard, GameMode::Endless));
+
+        let version_2 = decode(&sample_as_version(NO_DIFFICULTY_VERSION, NO_DIFFICULTY_HEADER_SIZE)).unwrap();
+        assert_eq!((version_2.difficulty, version_2.mode), (DifficultyPreset::Normal, GameMode::Endless));
+        assert_eq!(version_2.frames, sample().frames);
+        assert_eq!((version_2.seed, version_2.final_score), (0xdead_beef, 4200));
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() {
+        for version in [1, FORMAT_VERSION + 1] {
+            assert_eq!(decode(&sample_as_version(version, HEADER_SIZE)), Err(ReplayFormatError::UnsupportedVersion(version)));
+        }
+    }
+
+    #[test]
+    fn cut_off_replay_is_truncated() {
+        let bytes = encode(&sample());
+
+        assert_eq!(decode(&bytes[..NO_DIFFICULTY_HEADER_SIZE]), Err(ReplayFormatError::Truncated));
+        // A valid checksum over runs that end early.
+        let mut cut = bytes[..bytes.len() - CHECKSUM_SIZE - 2].to_vec();
+        let checksum = crc32fast::hash(&cut);
+        cut.extend_from_slice(&checksum.to_le_bytes());
+        assert_eq!(decode(&cut), Err(ReplayFormatError::Truncated));
+    }
+
+    #[test]
+    fn corrupted_replay_fails_the_checksum() {
+        let mut bytes = encode(&sample());
+        bytes[10] ^= 0xff;
+
+        assert_eq!(decode(&bytes), Err(ReplayFormatError::ChecksumMismatch));
+    }
+
+    #[test]
+    fn huge_run_lengths_are_rejected() {
+        let mut bytes = encode(&Replay { frames: Vec::new(), ..sample() });
+        bytes.truncate(bytes.len() - CHECKSUM_SIZE);
+        bytes[20..24].copy_from_slice(&2u32.to_le_bytes());
+        // Two runs that are each allowed, but not together.
+        for _ in 0..2 {
+            write_varint(&mut bytes, MAX_FRAMES);
+            bytes.extend_from_slice(&[0, 0]);
+        }
+        let checksum = crc32fast::hash(&bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+
+        assert_eq!(decode(&bytes), Err(ReplayFormatError::TooLong));
+    }
+}
@@ -0,0 +1,227 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+
+use crate::gameplay::{handle_input, DifficultyPreset, GameMode, GameRng, GameplaySet, GameplayState, PlayerInput, RequestedSeed, RestartEvent, Score};
+use crate::GameState;
+
+mod format;
+pub use format::ReplayFormatError;
+
+/// A recorded run: the seed, difficulty and mode it started from, the input of every
+/// simulation tick and the score it ended with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub difficulty: DifficultyPreset,
+    pub mode: GameMode,
+    pub final_score: i32,
+    pub frames: Vec<ReplayFrame>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayFrame {
+    /// Movement scaled to -127..=127.
+    pub movement: i8,
+    pub fire: bool,
+}
+
+impl ReplayFrame {
+    fn new(input: &PlayerInput) -> Self {
+        ReplayFrame { movement: (input.movement.clamp(-1.0, 1.0) * 127.0).round() as i8, fire: input.fire }
+    }
+
+    fn input(&self) -> PlayerInput {
+        PlayerInput { movement: self.movement as f32 / 127.0, fire: self.fire }
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    Format(ReplayFormatError),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(why) => write!(f, "i/o error: {why}"),
+            ReplayError::Format(why) => write!(f, "format error: {why}"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(why: io::Error) -> Self {
+        ReplayError::Io(why)
+    }
+}
+
+impl From<ReplayFormatError> for ReplayError {
+    fn from(why: ReplayFormatError) -> Self {
+        ReplayError::Format(why)
+    }
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        Ok(format::decode(&fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ReplayError> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, format::encode(self))?;
+        Ok(())
+    }
+}
+
+/// Where finished runs are saved. `None` disables saving replays.
+#[derive(Resource, Default)]
+pub struct ReplayDirectory(pub Option<PathBuf>);
+
+/// Input of the current run, recorded tick by tick.
+#[derive(Resource, Default)]
+pub struct ReplayRecorder {
+    frames: Vec<ReplayFrame>,
+}
+
+/// Plays a replay back instead of reading the keyboard.
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    replay: Replay,
+    cursor: usize,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        ReplayPlayback { replay, cursor: 0 }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.replay.seed
+    }
+
+    pub fn difficulty(&self) -> DifficultyPreset {
+        self.replay.difficulty
+    }
+
+    pub fn mode(&self) -> GameMode {
+        self.replay.mode
+    }
+
+    fn restart(&mut self) {
+        self.cursor = 0;
+    }
+}
+
+/// Records every run and plays back the one given with `--replay`.
+/// The recorded input is the input of each tick, so both happen in `GameplaySet::Input`.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(GameState::InGame), start_recording.run_if(not(resource_exists::<ReplayPlayback>)))
+            .add_systems(FixedUpdate, (
+                feed_playback_input.run_if(resource_exists::<ReplayPlayback>).before(handle_input),
+                record_frame.run_if(resource_exists::<ReplayRecorder>),
+            ).in_set(GameplaySet::Input))
+            .add_systems(OnEnter(GameplayState::GameOver), (
+                save_replay.run_if(resource_exists::<ReplayRecorder>),
+                verify_playback.run_if(resource_exists::<ReplayPlayback>)
+            ))
+            .add_systems(Update, reset_replay_on_restart.run_if(in_state(GameplayState::GameOver).or(in_state(GameplayState::Paused))))
+            .add_systems(OnExit(GameState::InGame), stop_playback.run_if(resource_exists::<ReplayPlayback>));
+    }
+}
+
+pub fn start_recording(
+    mut commands: Commands
+) {
+    commands.insert_resource(ReplayRecorder::default());
+}
+
+pub fn record_frame(
+    input: Res<PlayerInput>,
+    mut recorder: ResMut<ReplayRecorder>
+) {
+    recorder.frames.push(ReplayFrame::new(&input));
+}
+
+pub fn save_replay(
+    directory: Res<ReplayDirectory>,
+    rng: Res<GameRng>,
+    preset: Res<DifficultyPreset>,
+    mode: Res<GameMode>,
+    score: Res<Score>,
+    recorder: Res<ReplayRecorder>
+) {
+    let Some(directory) = &directory.0 else {
+        return;
+    };
+
+    let replay = Replay { seed: rng.seed, difficulty: *preset, mode: *mode, final_score: score.0, frames: recorder.frames.clone() };
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0);
+    let path = directory.join(format!("{timestamp}_{}.replay", score.0));
+    match replay.save(&path) {
+        Err(why) => error!("couldn't save replay to {}: {}", path.display(), why),
+        Ok(_) => info!("replay is saved: {}", path.display()),
+    }
+}
+
+pub fn reset_replay_on_restart(
+    mut event_reader: EventReader<RestartEvent>,
+    recorder: Option<ResMut<ReplayRecorder>>,
+    playback: Option<ResMut<ReplayPlayback>>
+) {
+    if event_reader.is_empty() {
+        return;
+    }
+    event_reader.clear();
+
+    if let Some(mut recorder) = recorder {
+        recorder.frames.clear();
+    }
+    if let Some(mut playback) = playback {
+        playback.restart();
+    }
+}
+
+pub fn feed_playback_input(
+    mut playback: ResMut<ReplayPlayback>,
+    mut input: ResMut<PlayerInput>
+) {
+    *input = match playback.replay.frames.get(playback.cursor) {
+        Some(frame) => frame.input(),
+        None => PlayerInput::default(),
+    };
+    playback.cursor += 1;
+}
+
+pub fn verify_playback(
+    playback: Res<ReplayPlayback>,
+    score: Res<Score>
+) {
+    if playback.replay.final_score == score.0 {
+        info!("replay verified: score {}", score.0);
+    }
+    else {
+        warn!("replay diverged: recorded score {}, played back {}", playback.replay.final_score, score.0);
+    }
+}
+
+pub fn stop_playback(
+    mut requested_seed: ResMut<RequestedSeed>,
+    mut commands: Commands
+) {
+    requested_seed.0 = None;
+    commands.remove_resource::<ReplayPlayback>();
+}
@@ -1,24 +1,23 @@
 use std::fmt::Display;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use bevy::prelude::*;
-use bevy::ui::RelativeCursorPosition;
-use bevy_ecs::observer::TriggerTargets;
+use bevy::window::WindowFocused;
 use bevy_ecs::relationship::RelatedSpawnerCommands;
 use bevy::render::camera::ScalingMode;
 
-use crate::database;
+use crate::database::{self, LeaderboardEntry};
 use crate::gameplay::*;
 use crate::GameState;
 use crate::audio::*;
+use crate::replay::ReplayPlayback;
 
-mod slider;
-use slider::*;
+mod plugin;
+pub mod widgets;
+pub use plugin::UiPlugin;
+use widgets::*;
 
-const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
-const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
-const PRESSED_BUTTON: Color = Color::srgb(0.35, 0.75, 0.35);
-
-const DEFAULT_MARGIN: UiRect = UiRect::all(Val::Px(5.0));
+const INITIALS_LENGTH: usize = 3;
 
 #[derive(Component)]
 pub struct Hud;
@@ -29,16 +28,43 @@ pub struct HealthText;
 #[derive(Component)]
 pub struct ScoreText;
 
+#[derive(Component)]
+pub struct DifficultyLevelText;
+
+#[derive(Component)]
+pub struct PowerUpText;
+
+#[derive(Component)]
+pub struct CampaignLevelText;
+
+#[derive(Component)]
+pub struct BossHealthBar;
+
+#[derive(Component)]
+pub struct BossHealthFill;
+
 #[derive(Component)]
 pub struct ScoreRecordText;
 
+/// The cycle that picks the difficulty preset in the main menu.
+#[derive(Component)]
+pub struct DifficultyCycle;
+
 #[derive(Component)]
 pub struct GameOverPanel;
 
+#[derive(Component)]
+pub struct NameEntryPanel;
+
+/// The text input of the initials for a leaderboard run.
+#[derive(Component)]
+pub struct NameEntry;
+
 #[derive(Component)]
 pub enum GameOverPanelButtonAction {
     Restart,
     ExitToMenu,
+    SaveScore,
 }
 
 pub fn setup_hud(
@@ -78,8 +104,61 @@ pub fn setup_hud(
             TextSpan::default(),
             HealthText
         ));
+            parent.spawn((
+                Text::new("Level: "),
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(5.0),
+                    top: Val::Px(40.0),
+                    ..default()
+                }
+            ))
+            .with_child((
+                TextSpan::default(),
+                DifficultyLevelText
+            ));
+            parent.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(5.0),
+                    top: Val::Px(80.0),
+                    ..default()
+                },
+                PowerUpText
+            ));
         });
 
+        parent.spawn((
+            Node {
+                display: bevy::ui::Display::None,
+                width: Val::Percent(80.0),
+                height: Val::Px(16.0),
+                grid_column: GridPlacement::start(2),
+                grid_row: GridPlacement::start(1),
+                align_self: AlignSelf::Start,
+                justify_self: JustifySelf::Center,
+                margin: UiRect::top(Val::Px(10.0)),
+                padding: UiRect::all(Val::Px(2.0)),
+                ..Default::default()
+            },
+            BackgroundColor(Color::BLACK),
+            BossHealthBar
+        ))
+        .with_child((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..Default::default()
+            },
+            BackgroundColor(Color::srgb(0.85, 0.2, 0.2)),
+            BossHealthFill
+        ));
+
         parent.spawn((
             Node {
                 height: Val::Percent(100.0),
@@ -103,6 +182,20 @@ pub fn setup_hud(
                 TextSpan::default(),
                 ScoreText
             ));
+            parent.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                Node {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(5.0),
+                    top: Val::Px(40.0),
+                    ..default()
+                },
+                CampaignLevelText
+            ));
         });
     });
 }
@@ -146,16 +239,21 @@ pub fn cleanup_hud(
 }
 
 pub fn game_over_panel_action(
-    interaction_query: Query<
-        (&Interaction, &GameOverPanelButtonAction),
-        (Changed<Interaction>, With<Button>)
-    >,
+    mut pressed_events: EventReader<ButtonPressed>,
+    actions: Query<&GameOverPanelButtonAction>,
+    name_entry: Option<Single<(&TextInput, &ChildOf), With<NameEntry>>>,
+    score: Res<Score>,
+    stats: Res<RunStats>,
+    preset: Res<DifficultyPreset>,
+    mut leaderboard: ResMut<Leaderboard>,
+    mut storage: ResMut<database::Storage>,
     mut game_over_writer: EventWriter<RestartEvent>,
     mut game_state: ResMut<NextState<GameState>>,
-    mut gameplay_state: ResMut<NextState<GameplayState>>
+    mut gameplay_state: ResMut<NextState<GameplayState>>,
+    mut commands: Commands
 ) {
-    for (interaction, action) in interaction_query {
-        if *interaction == Interaction::Pressed {
+    for event in pressed_events.read() {
+        if let Ok(action) = actions.get(event.entity) {
             match action {
                 GameOverPanelButtonAction::Restart => {
                     game_over_writer.write_default();
@@ -164,6 +262,29 @@ pub fn game_over_panel_action(
                     gameplay_state.set(GameplayState::None);
                     game_state.set(GameState::MainMenu);
                 }
+                GameOverPanelButtonAction::SaveScore => {
+                    let Some((name_entry, panel)) = name_entry.as_deref() else {
+                        continue;
+                    };
+                    if name_entry.value.is_empty() {
+                        continue;
+                    }
+
+                    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0);
+                    let entry = LeaderboardEntry {
+                        initials: name_entry.value.clone(),
+                        difficulty: preset.key().to_string(),
+                        timestamp,
+                        score: score.0,
+                        duration: stats.duration,
+                        asteroids_destroyed: stats.asteroids_destroyed,
+                        shots_fired: stats.shots_fired,
+                    };
+                    if leaderboard.insert(entry).is_some() {
+                        storage.update(|data| data.leaderboard = leaderboard.0.clone());
+                    }
+                    commands.entity(panel.parent()).despawn();
+                }
             }
         }
     }
@@ -189,17 +310,78 @@ pub fn update_score_ui(
     }
 }
 
+pub fn update_power_up_ui(
+    power_ups: Res<ActivePowerUps>,
+    mut text_query: Query<&mut Text, With<PowerUpText>>
+) {
+    let value = power_ups.remaining()
+        .map(|(power_up, seconds)| format!("{power_up}: {seconds:.1}s"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    for mut text in &mut text_query {
+        text.0.clone_from(&value);
+    }
+}
+
+pub fn update_campaign_level_ui(
+    director: Res<LevelDirector>,
+    mut text_query: Query<&mut Text, With<CampaignLevelText>>
+) {
+    let value = match &director.level_name {
+        Some(name) => format!("Stage {}: {name}", director.level + 1),
+        None => String::new(),
+    };
+    for mut text in &mut text_query {
+        text.0.clone_from(&value);
+    }
+}
+
+// With more than one boss alive, the bar shows the weakest one.
+pub fn update_boss_health_ui(
+    bosses: Query<(&Health, &Boss)>,
+    mut bar: Single<&mut Node, With<BossHealthBar>>,
+    mut fill: Single<&mut Node, (With<BossHealthFill>, Without<BossHealthBar>)>
+) {
+    let weakest = bosses.iter()
+        .map(|(health, boss)| health.0.max(0) as f32 / boss.max_health.max(1) as f32)
+        .min_by(f32::total_cmp);
+    match weakest {
+        Some(share) => {
+            bar.display = bevy::ui::Display::Flex;
+            fill.width = Val::Percent(share * 100.0);
+        }
+        None => {
+            bar.display = bevy::ui::Display::None;
+        }
+    }
+}
+
 pub fn handle_game_over(
     current_score: Res<Score>,
     record_score: Res<ScoreRecord>,
+    preset: Res<DifficultyPreset>,
+    leaderboard: Res<Leaderboard>,
+    rng: Res<GameRng>,
+    playback: Option<Res<ReplayPlayback>>,
+    director: Option<Res<LevelDirector>>,
     commands: Commands
 ) {
-    spawn_game_over_panel(current_score.0, record_score.0, commands)
+    let is_leaderboard_run = playback.is_none() && leaderboard.qualifies(current_score.0);
+    let title = match director {
+        Some(director) if director.load_failed => "CAMPAIGN FAILED TO LOAD",
+        Some(director) if director.finished => "CAMPAIGN COMPLETE!",
+        _ => "GAME OVER!",
+    };
+    spawn_game_over_panel(title, current_score.0, record_score.get(*preset), *preset, rng.seed, is_leaderboard_run, commands)
 }
 
 fn spawn_game_over_panel(
+    title: &str,
     current_score: i32,
     record_score: i32,
+    preset: DifficultyPreset,
+    seed: u64,
+    is_leaderboard_run: bool,
     mut commands: Commands
 ) {
     commands.spawn((
@@ -221,9 +403,28 @@ fn spawn_game_over_panel(
         GameOverPanel
     ))
     .with_children(|parent| {
-        create_text(parent, 40.0, "GAME OVER!");
+        create_text(parent, 40.0, title);
         create_text(parent, 20.0, &format!("Score: {current_score}"));
-        create_text(parent, 20.0, &format!("Your record: {record_score}"));
+        create_text(parent, 20.0, &format!("Your record on {preset}: {record_score}"));
+        create_text(parent, 16.0, &format!("Seed: {seed}"));
+
+        if is_leaderboard_run {
+            parent.spawn((
+                Node {
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                NameEntryPanel
+            ))
+            .with_children(|parent| {
+                create_text(parent, 20.0, "New top 10 run! Your initials:");
+                let initials = TextInput::new(INITIALS_LENGTH)
+                    .with_filter(|character| character.is_ascii_alphanumeric().then(|| character.to_ascii_uppercase()));
+                create_text_input(parent, 33.0, initials, NameEntry);
+                create_button(parent, 120.0, 50.0, "Save", GameOverPanelButtonAction::SaveScore);
+            });
+        }
         
         parent.spawn((
             Node {
@@ -239,11 +440,168 @@ fn spawn_game_over_panel(
     });
 }
 
+#[derive(Component)]
+pub struct PauseMenu;
+
+/// The volume page of the pause menu.
+#[derive(Component)]
+pub struct PauseSettings;
+
+#[derive(Component)]
+pub enum PauseMenuButtonAction {
+    Resume,
+    Settings,
+    Back,
+    Restart,
+    ExitToMenu,
+}
+
+pub fn setup_pause_menu(
+    commands: Commands
+) {
+    spawn_pause_panel(commands);
+}
+
+pub fn cleanup_pause_menu(
+    menus: Query<Entity, With<PauseMenu>>,
+    settings: Query<(), With<PauseSettings>>,
+    sound_volume: Res<SoundVolume>,
+    music_volume: Res<MusicVolume>,
+    mut storage: ResMut<database::Storage>,
+    mut commands: Commands
+) {
+    if !settings.is_empty() {
+        save_volumes(&mut storage, &sound_volume, &music_volume);
+    }
+    for menu in menus {
+        commands.entity(menu).despawn();
+    }
+}
+
+pub fn pause_menu_action(
+    mut pressed_events: EventReader<ButtonPressed>,
+    actions: Query<&PauseMenuButtonAction>,
+    menus: Query<Entity, With<PauseMenu>>,
+    sound_volume: Res<SoundVolume>,
+    music_volume: Res<MusicVolume>,
+    mut storage: ResMut<database::Storage>,
+    mut restart_writer: EventWriter<RestartEvent>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut gameplay_state: ResMut<NextState<GameplayState>>,
+    mut commands: Commands
+) {
+    for event in pressed_events.read() {
+        let Ok(action) = actions.get(event.entity) else {
+            continue;
+        };
+        match action {
+            PauseMenuButtonAction::Resume => {
+                gameplay_state.set(GameplayState::Game);
+            }
+            PauseMenuButtonAction::Settings => {
+                for menu in &menus {
+                    commands.entity(menu).despawn();
+                }
+                spawn_pause_settings_panel(sound_volume.0, music_volume.0, commands.reborrow());
+            }
+            PauseMenuButtonAction::Back => {
+                save_volumes(&mut storage, &sound_volume, &music_volume);
+                for menu in &menus {
+                    commands.entity(menu).despawn();
+                }
+                spawn_pause_panel(commands.reborrow());
+            }
+            PauseMenuButtonAction::Restart => {
+                restart_writer.write_default();
+            }
+            PauseMenuButtonAction::ExitToMenu => {
+                gameplay_state.set(GameplayState::None);
+                game_state.set(GameState::MainMenu);
+            }
+        }
+    }
+}
+
+/// Pauses the run when the game window loses focus.
+pub fn pause_on_focus_lost(
+    mut focus_events: EventReader<WindowFocused>,
+    state: Res<State<GameplayState>>,
+    mut next_state: ResMut<NextState<GameplayState>>
+) {
+    let focus_lost = focus_events.read().any(|event| !event.focused);
+    if focus_lost && *state.get() == GameplayState::Game {
+        next_state.set(GameplayState::Paused);
+    }
+}
+
+fn pause_panel_node() -> Node {
+    Node {
+        width: Val::Percent(40.0),
+        height: Val::Percent(60.0),
+        align_self: AlignSelf::Center,
+        align_items: AlignItems::Center,
+        flex_direction: FlexDirection::Column,
+        justify_content: JustifyContent::Center,
+        justify_self: JustifySelf::Center,
+        ..Default::default()
+    }
+}
+
+fn spawn_pause_panel(
+    mut commands: Commands
+) {
+    commands.spawn((
+        pause_panel_node(),
+        BackgroundColor(Color::srgba(0.3, 0.3, 0.3, 0.9)),
+        PauseMenu
+    ))
+    .with_children(|parent| {
+        create_text(parent, 40.0, "PAUSED");
+        create_button(parent, 300.0, 70.0, "Resume", PauseMenuButtonAction::Resume);
+        create_button(parent, 300.0, 70.0, "Settings", PauseMenuButtonAction::Settings);
+        create_button(parent, 300.0, 70.0, "Restart", PauseMenuButtonAction::Restart);
+        create_button(parent, 300.0, 70.0, "Exit to menu", PauseMenuButtonAction::ExitToMenu);
+    });
+}
+
+fn spawn_pause_settings_panel(
+    sound_volume: f32,
+    music_volume: f32,
+    mut commands: Commands
+) {
+    commands.spawn((
+        pause_panel_node(),
+        BackgroundColor(Color::srgba(0.3, 0.3, 0.3, 0.9)),
+        PauseMenu,
+        PauseSettings
+    ))
+    .with_children(|parent| {
+        create_text(parent, 40.0, "Settings");
+        create_volume_sliders(parent, music_volume, sound_volume);
+        create_button(parent, 300.0, 70.0, "Back", PauseMenuButtonAction::Back);
+    });
+}
+
+fn save_volumes(
+    storage: &mut database::Storage,
+    sound_volume: &SoundVolume,
+    music_volume: &MusicVolume
+) {
+    storage.update(|data| {
+        data.sound_volume = sound_volume.0;
+        data.music_volume = music_volume.0;
+    });
+}
+
 #[derive(Component)]
 pub struct MainMenu;
 #[derive(Component)]
 pub struct SettingsMenu;
 #[derive(Component)]
+pub struct LeaderboardMenu;
+#[derive(Component)]
+pub struct ControlsMenu;
+#[derive(Component)]
 pub struct MusicVolumeText;
 #[derive(Component)]
 pub struct SoundVolumeText;
@@ -252,6 +610,8 @@ pub struct SoundVolumeText;
 pub enum MenuState {
     MainMenu,
     Settings,
+    Leaderboard,
+    Controls,
     #[default]
     Disabled
 }
@@ -259,11 +619,31 @@ pub enum MenuState {
 #[derive(Component)]
 pub enum MenuButtonAction {
     Play,
+    Campaign,
     Settings,
+    Leaderboard,
     Reset,
+    Controls,
+    ResetControls,
+    ExitToSettings,
     ExitToMainMenu,
     Exit,
 }
+
+/// A key slot of an action on the controls page. Clicking it waits for the key to bind.
+#[derive(Component, Clone, Copy, PartialEq)]
+pub struct BindingButton {
+    pub action: Action,
+    pub slot: usize,
+}
+
+/// Label of a `BindingButton`.
+#[derive(Component)]
+pub struct BindingText(pub BindingButton);
+
+/// The key slot waiting for a key press.
+#[derive(Resource)]
+pub struct PendingRebind(pub BindingButton);
 #[derive(Component)]
 pub enum MenuSliderAction {
     Music,
@@ -277,6 +657,7 @@ pub fn setup_menu(
 }
 
 pub fn setup_main_menu(
+    preset: Res<DifficultyPreset>,
     mut commands: Commands
 ) {
     commands.spawn((
@@ -294,22 +675,33 @@ pub fn setup_main_menu(
     ))
     .with_children(|parent| {
         create_text(parent, 50.0, "Rust-Shooter");
-        create_button(parent, 300.0, 90.0, "Play", MenuButtonAction::Play);
-        parent.spawn((
+        create_button(parent, 300.0, 90.0, "Endless", MenuButtonAction::Play);
+        create_button(parent, 300.0, 90.0, "Campaign", MenuButtonAction::Campaign);
+        create_cycle(parent, 300.0, 60.0, "Difficulty: ", Cycle::new(DifficultyPreset::ALL, preset.index()), DifficultyCycle);
+        parent.spawn(
             Node {
-                width: Val::Px(300.0),
+                align_items: AlignItems::Center,
                 margin: UiRect { bottom: Val::Px(15.0), ..DEFAULT_MARGIN },
                 ..Default::default()
-            },
-            Text::new("Your record: "),
-            TextFont {
-                font_size: 18.0,
-                ..Default::default()
-            }
-            )).with_child((
-                TextSpan::default(),
-                ScoreRecordText
-            ));
+            })
+            .with_children(|parent| {
+                parent.spawn((
+                    Node {
+                        width: Val::Px(150.0),
+                        margin: DEFAULT_MARGIN,
+                        ..Default::default()
+                    },
+                    Text::new("Your record: "),
+                    TextFont {
+                        font_size: 18.0,
+                        ..Default::default()
+                    }
+                    )).with_child((
+                        TextSpan::default(),
+                        ScoreRecordText
+                    ));
+                create_button(parent, 210.0, 50.0, "Leaderboard", MenuButtonAction::Leaderboard);
+            });
         create_button(parent, 300.0, 90.0, "Settings", MenuButtonAction::Settings);
         create_button(parent, 300.0, 90.0, "Reset record", MenuButtonAction::Reset);
         create_button(parent, 300.0, 90.0, "Exit", MenuButtonAction::Exit);
@@ -343,54 +735,8 @@ pub fn setup_settings_menu(
     ))
     .with_children(|parent| {
         create_text(parent, 50.0, "Settings");
-        parent.spawn(
-            Node {
-                width: Val::Px(350.0),
-                ..Default::default()
-            })
-            .with_children(|parent| {
-                create_text(parent, 25.0, "Music: ");
-                create_slider(parent, 300.0, 50.0, 0.0, 100.0, music_volume.0, MenuSliderAction::Music);
-                parent.spawn((
-                    Node {
-                        width: Val::Px(30.0),
-                        margin: DEFAULT_MARGIN,
-                        ..Default::default()
-                    },
-                    Text::default(),
-                    TextFont {
-                        font_size: 25.0,
-                        ..Default::default()
-                    }
-                )).with_child((
-                    TextSpan::default(),
-                    MusicVolumeText
-                ));
-            });
-        parent.spawn(
-            Node {
-                width: Val::Px(350.0),
-                ..Default::default()
-            })
-            .with_children(|parent| {
-                create_text(parent, 25.0, "Sound: ");
-                create_slider(parent, 250.0, 50.0, 0.0, 100.0, sound_volume.0, MenuSliderAction::Sound);
-                parent.spawn((
-                    Node {
-                        width: Val::Px(30.0),
-                        margin: DEFAULT_MARGIN,
-                        ..Default::default()
-                    },
-                    Text::default(),
-                    TextFont {
-                        font_size: 25.0,
-                        ..Default::default()
-                    }
-                )).with_child((
-                    TextSpan::default(),
-                    SoundVolumeText
-                ));
-            });
+        create_volume_sliders(parent, music_volume.0, sound_volume.0);
+        create_button(parent, 300.0, 90.0, "Controls", MenuButtonAction::Controls);
         create_button(parent, 300.0, 90.0, "Exit", MenuButtonAction::ExitToMainMenu);
     });
 }
@@ -402,38 +748,229 @@ pub fn cleanup_settings_menu(
     commands.entity(settings_menu.entity()).despawn();
 }
 
+pub fn setup_leaderboard_menu(
+    leaderboard: Res<Leaderboard>,
+    mut commands: Commands
+) {
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..Default::default()
+        },
+        BackgroundColor(Color::srgb(0.3, 0.3, 0.3)),
+        Visibility::Visible,
+        LeaderboardMenu
+    ))
+    .with_children(|parent| {
+        create_text(parent, 50.0, "Leaderboard");
+        if leaderboard.0.is_empty() {
+            create_text(parent, 25.0, "No runs yet. Go and set a record!");
+        }
+        else {
+            parent.spawn((
+                Node {
+                    display: bevy::ui::Display::Grid,
+                    grid_template_columns: RepeatedGridTrack::auto(8),
+                    column_gap: Val::Px(20.0),
+                    margin: DEFAULT_MARGIN,
+                    ..Default::default()
+                },
+            ))
+            .with_children(|parent| {
+                for header in ["#", "Name", "Difficulty", "Score", "Time", "Asteroids", "Shots", "Date"] {
+                    create_text(parent, 22.0, header);
+                }
+                for (place, entry) in leaderboard.0.iter().enumerate() {
+                    create_text(parent, 20.0, &format!("{}", place + 1));
+                    create_text(parent, 20.0, &entry.initials);
+                    let difficulty = DifficultyPreset::from_key(&entry.difficulty).map(|preset| preset.to_string());
+                    create_text(parent, 20.0, difficulty.as_deref().unwrap_or(&entry.difficulty));
+                    create_text(parent, 20.0, &format!("{}", entry.score));
+                    create_text(parent, 20.0, &format_duration(entry.duration));
+                    create_text(parent, 20.0, &format!("{}", entry.asteroids_destroyed));
+                    create_text(parent, 20.0, &format!("{}", entry.shots_fired));
+                    create_text(parent, 20.0, &format_date(entry.timestamp));
+                }
+            });
+        }
+        create_button(parent, 300.0, 90.0, "Back", MenuButtonAction::ExitToMainMenu);
+    });
+}
+
+pub fn cleanup_leaderboard_menu(
+    leaderboard_menu: Single<Entity, With<LeaderboardMenu>>,
+    mut commands: Commands
+) {
+    commands.entity(leaderboard_menu.entity()).despawn();
+}
+
+pub fn setup_controls_menu(
+    mut commands: Commands
+) {
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..Default::default()
+        },
+        BackgroundColor(Color::srgb(0.3, 0.3, 0.3)),
+        Visibility::Visible,
+        ControlsMenu
+    ))
+    .with_children(|parent| {
+        create_text(parent, 50.0, "Controls");
+        create_text(parent, 20.0, "Click a key and press the new one. Backspace unbinds it.");
+        parent.spawn((
+            Node {
+                display: bevy::ui::Display::Grid,
+                grid_template_columns: RepeatedGridTrack::auto(1 + BINDINGS_PER_ACTION as u16),
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(20.0),
+                margin: DEFAULT_MARGIN,
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            for action in Action::ALL {
+                create_text(parent, 25.0, &action.to_string());
+                for slot in 0..BINDINGS_PER_ACTION {
+                    let button = BindingButton { action, slot };
+                    create_value_button(parent, 220.0, 60.0, "", BindingText(button), button);
+                }
+            }
+        });
+        create_button(parent, 300.0, 90.0, "Reset to defaults", MenuButtonAction::ResetControls);
+        create_button(parent, 300.0, 90.0, "Back", MenuButtonAction::ExitToSettings);
+    });
+}
+
+pub fn cleanup_controls_menu(
+    controls_menu: Single<Entity, With<ControlsMenu>>,
+    mut commands: Commands
+) {
+    commands.entity(controls_menu.entity()).despawn();
+    commands.remove_resource::<PendingRebind>();
+}
+
+pub fn binding_button_action(
+    mut pressed_events: EventReader<ButtonPressed>,
+    buttons: Query<&BindingButton>,
+    mut commands: Commands
+) {
+    for event in pressed_events.read() {
+        if let Ok(button) = buttons.get(event.entity) {
+            commands.insert_resource(PendingRebind(*button));
+        }
+    }
+}
+
+pub fn capture_rebind(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    pending: Res<PendingRebind>,
+    mut bindings: ResMut<Bindings>,
+    mut storage: ResMut<database::Storage>,
+    mut commands: Commands
+) {
+    // The key that started the rebind, such as Enter, isn't the new binding.
+    if pending.is_added() {
+        return;
+    }
+    // Keys without a known key code can't be saved.
+    let Some(key) = keyboard.get_just_pressed().find(|key| !matches!(key, KeyCode::Unidentified(_))) else {
+        return;
+    };
+
+    let BindingButton { action, slot } = pending.0;
+    if *key == KeyCode::Backspace {
+        bindings.clear(action, slot);
+    }
+    else {
+        bindings.bind(action, slot, *key);
+    }
+    storage.update(|data| {
+        data.bindings = bindings.to_saved();
+    });
+    commands.remove_resource::<PendingRebind>();
+}
+
+pub fn update_binding_text(
+    text_query: Query<(&mut TextSpan, &BindingText)>,
+    bindings: Res<Bindings>,
+    pending: Option<Res<PendingRebind>>
+) {
+    for (mut text, BindingText(button)) in text_query {
+        **text = if pending.as_ref().is_some_and(|pending| pending.0 == *button) {
+            "Press a key...".to_string()
+        }
+        else {
+            bindings.get(button.action, button.slot).map(key_label).unwrap_or_else(|| "-".to_string())
+        };
+    }
+}
+
 pub fn menu_button_action(
-    interaction_query: Query<
-        (&Interaction, &MenuButtonAction),
-        (Changed<Interaction>, With<Button>),
-    >,
+    mut pressed_events: EventReader<ButtonPressed>,
+    actions: Query<&MenuButtonAction>,
     mut app_exit_events: EventWriter<AppExit>,
     mut game_state: ResMut<NextState<GameState>>,
     mut menu_state: ResMut<NextState<MenuState>>,
     mut record: ResMut<ScoreRecord>,
+    preset: Res<DifficultyPreset>,
+    mut mode: ResMut<GameMode>,
+    mut storage: ResMut<database::Storage>,
+    mut bindings: ResMut<Bindings>,
     sound_volume: Res<SoundVolume>,
     music_volume: Res<MusicVolume>
 ) {
-    for (interaction, action) in interaction_query {
-        if *interaction == Interaction::Pressed {
+    for event in pressed_events.read() {
+        if let Ok(action) = actions.get(event.entity) {
             match action {
                 MenuButtonAction::Exit => {
                     app_exit_events.write_default();
                 }
                 MenuButtonAction::Play => {
+                    *mode = GameMode::Endless;
+                    menu_state.set(MenuState::Disabled);
+                    game_state.set(GameState::InGame);
+                }
+                MenuButtonAction::Campaign => {
+                    *mode = GameMode::Campaign;
                     menu_state.set(MenuState::Disabled);
                     game_state.set(GameState::InGame);
                 }
                 MenuButtonAction::Settings => {
                     menu_state.set(MenuState::Settings);
                 }
+                MenuButtonAction::Leaderboard => {
+                    menu_state.set(MenuState::Leaderboard);
+                }
                 MenuButtonAction::Reset => {
-                    record.0 = 0;
-                    database::save_record(0);
+                    record.set(*preset, 0);
+                    storage.update(|data| {
+                        data.records.remove(preset.key());
+                    });
+                }
+                MenuButtonAction::Controls => {
+                    menu_state.set(MenuState::Controls);
+                }
+                MenuButtonAction::ResetControls => {
+                    *bindings = Bindings::default();
+                    storage.update(|data| {
+                        data.bindings.clear();
+                    });
+                }
+                MenuButtonAction::ExitToSettings => {
+                    menu_state.set(MenuState::Settings);
                 }
                 MenuButtonAction::ExitToMainMenu => {
-                    database::save_sound_volume(sound_volume.0);
-                    database::save_music_volume(music_volume.0);
+                    save_volumes(&mut storage, &sound_volume, &music_volume);
                     menu_state.set(MenuState::MainMenu);
                 }
             }
@@ -441,21 +978,33 @@ pub fn menu_button_action(
     }
 }
 
+pub fn difficulty_cycle_action(
+    mut changed_events: EventReader<CycleChanged>,
+    cycles: Query<(), With<DifficultyCycle>>,
+    mut preset: ResMut<DifficultyPreset>
+) {
+    for event in changed_events.read() {
+        if cycles.contains(event.entity) {
+            *preset = DifficultyPreset::ALL[event.index];
+        }
+    }
+}
+
 pub fn menu_slider_action(
-    interaction_query: Query<(&Interaction, &Slider, &MenuSliderAction)>,
+    mut changed_events: EventReader<SliderChanged>,
+    actions: Query<&MenuSliderAction>,
     mut music_volume: ResMut<MusicVolume>,
     mut sound_volume: ResMut<SoundVolume>,
 ) {
-    for (interaction, slider, action) in interaction_query {
-        if *interaction == Interaction::Pressed {
-            match action {
-                MenuSliderAction::Music => {
-                    music_volume.0 = slider.get_absolute_value();
-                },
-                MenuSliderAction::Sound => {
-                    sound_volume.0 = slider.get_absolute_value();
-                }
+    for event in changed_events.read() {
+        match actions.get(event.entity) {
+            Ok(MenuSliderAction::Music) => {
+                music_volume.0 = event.value;
+            },
+            Ok(MenuSliderAction::Sound) => {
+                sound_volume.0 = event.value;
             }
+            Err(_) => (),
         }
     }
 }
@@ -469,152 +1018,93 @@ pub fn resource_value_text<T, R> (
 {
     let value = value.into_inner().to_string();
     for mut text in text_query {
-        **text = format!("{value}");
-    }
-}
-
-pub fn button_system(
-    interaction_query: Query<(
-        &Interaction,
-        &mut BackgroundColor
-    ),
-    (Changed<Interaction>, With<Button>)>
-) {
-    for (interaction, mut background_color) in interaction_query {
-        match *interaction {
-            Interaction::Pressed => {
-                *background_color = PRESSED_BUTTON.into();
-            }
-            Interaction::Hovered => {
-                *background_color = HOVERED_BUTTON.into();
-            }
-            Interaction::None => {
-                *background_color = NORMAL_BUTTON.into();
-            }
-        }
+        **text = value.clone();
     }
 }
 
-pub fn slider_system(
-    sliders: Query<(&RelativeCursorPosition, &Interaction, &Children, &mut Slider)>,
-    mut slider_bars: Query<&mut Node, With<SliderBar>>
+pub fn update_score_record_text(
+    text_query: Query<&mut TextSpan, With<ScoreRecordText>>,
+    record: Res<ScoreRecord>,
+    preset: Res<DifficultyPreset>
 ) {
-    for (pos, interaction, children, mut slider) in sliders {
-        if *interaction == Interaction::Pressed {
-            match pos.normalized {
-                Some(vec) => {
-                    slider.set_value(vec.x);
-                },
-                None => ()
-            }
-
-            for child in children.entities() {
-                let node = slider_bars.get_mut(child);
-                match node {
-                    Result::Err(err) => {
-                        println!("Warning: {}", err);
-                    }
-                    Result::Ok(mut node) => {
-                        node.width = Val::Percent(slider.value * 100.0);
-                    }
-                }
-            }
-        }
+    let value = record.get(*preset).to_string();
+    for mut text in text_query {
+        **text = value.clone();
     }
 }
 
-fn create_text(
-    parent: &mut RelatedSpawnerCommands<'_, ChildOf>, 
-    size: f32,
-    text: &str
-) {
-    parent.spawn((
-        Node {
-            margin: DEFAULT_MARGIN,
-            ..Default::default()
-        },
-        Text::new(text),
-        TextFont {
-            font_size: size,
-            ..Default::default()
-        }
-    ));
-}
-
-fn create_button<A: Component>(
+// Music and sound volume rows, shared by the settings menu and the pause menu.
+fn create_volume_sliders(
     parent: &mut RelatedSpawnerCommands<'_, ChildOf>,
-    width: f32,
-    height: f32, 
-    button_text: &str,
-    button_action: A
+    music_volume: f32,
+    sound_volume: f32
 ) {
-    parent.spawn((
-        Button,
+    parent.spawn(
         Node {
-            width: Val::Px(width),
-            height: Val::Px(height),
-            border: UiRect::all(Val::Px(5.0)),
-            justify_content: JustifyContent::Center,
-            align_content: AlignContent::Center,
-            align_items: AlignItems::Center,
-            margin: DEFAULT_MARGIN,
+            width: Val::Px(350.0),
             ..Default::default()
-        },
-        BorderColor(Color::BLACK),
-        BorderRadius::MAX,
-        BackgroundColor(NORMAL_BUTTON),
-        button_action,
-        children![(
-            Text::new(button_text),
-            TextFont {
-                font_size: 33.0,
-                ..default()
-            },
-            TextColor(Color::srgb(0.9, 0.9, 0.9)),
-            TextShadow::default()
-        )],
-    ));
-}
-
-fn create_slider<A: Component>(
-    parent: &mut RelatedSpawnerCommands<'_, ChildOf>,
-    width: f32,
-    height: f32,
-    min: f32,
-    max: f32,
-    value: f32,
-    slider_action: A
-) {
-    parent.spawn((
+        })
+        .with_children(|parent| {
+            create_text(parent, 25.0, "Music: ");
+            create_slider(parent, 300.0, 50.0, Slider::new(0.0, 100.0, music_volume).with_step(1.0), MenuSliderAction::Music);
+            parent.spawn((
+                Node {
+                    width: Val::Px(30.0),
+                    margin: DEFAULT_MARGIN,
+                    ..Default::default()
+                },
+                Text::default(),
+                TextFont {
+                    font_size: 25.0,
+                    ..Default::default()
+                }
+            )).with_child((
+                TextSpan::default(),
+                MusicVolumeText
+            ));
+        });
+    parent.spawn(
         Node {
-            width: Val::Px(width),
-            height: Val::Px(height),
-            padding: UiRect::all(Val::Px(5.0)),
+            width: Val::Px(350.0),
             ..Default::default()
-        },
-        BackgroundColor(Color::BLACK)
-    ))
-    .with_children(|parent| {
-        let interpolated_value = (value - min) / (max - min);
-        parent.spawn((
-            Node {
-                width: Val::Percent(100.0),
-                height: Val::Percent(100.0),
-                ..Default::default()
-            },
-            Slider { min: min, max: max, value: interpolated_value },
-            slider_action
-        ))
+        })
         .with_children(|parent| {
+            create_text(parent, 25.0, "Sound: ");
+            create_slider(parent, 250.0, 50.0, Slider::new(0.0, 100.0, sound_volume).with_step(1.0), MenuSliderAction::Sound);
             parent.spawn((
                 Node {
-                    width: Val::Percent(interpolated_value * 100.0),
-                    height: Val::Percent(100.0),
+                    width: Val::Px(30.0),
+                    margin: DEFAULT_MARGIN,
                     ..Default::default()
                 },
-                BackgroundColor(Color::WHITE),
-                SliderBar
+                Text::default(),
+                TextFont {
+                    font_size: 25.0,
+                    ..Default::default()
+                }
+            )).with_child((
+                TextSpan::default(),
+                SoundVolumeText
             ));
         });
-    });
-}
\ No newline at end of file
+}
+
+fn format_duration(seconds: f32) -> String {
+    let seconds = seconds.max(0.0) as u32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+// Formats a Unix timestamp as a UTC `YYYY-MM-DD` date.
+fn format_date(timestamp: u64) -> String {
+    // Days-to-civil conversion from Howard Hinnant's date algorithms.
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
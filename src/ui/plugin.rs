@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+use bevy::render::camera::ScalingMode;
+
+use crate::audio::{MusicVolume, SoundVolume};
+use crate::gameplay::{DifficultyLevel, GameplayState, LevelDirector};
+use crate::GameState;
+use super::*;
+
+/// The camera, the menus, the HUD and the game over panel.
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugins(WidgetPlugin)
+            .init_state::<MenuState>()
+            // Keys go to the binding being changed instead while the controls page waits for one.
+            .configure_sets(PreUpdate, FocusNavigation.run_if(not(resource_exists::<PendingRebind>)))
+
+            .add_systems(Startup, setup_camera)
+
+            .add_systems(OnEnter(GameState::MainMenu), setup_menu)
+            .add_systems(OnEnter(MenuState::MainMenu), setup_main_menu)
+            .add_systems(OnExit(MenuState::MainMenu), cleanup_main_menu)
+            .add_systems(OnEnter(MenuState::Settings), setup_settings_menu)
+            .add_systems(OnExit(MenuState::Settings), cleanup_settings_menu)
+            .add_systems(OnEnter(MenuState::Leaderboard), setup_leaderboard_menu)
+            .add_systems(OnExit(MenuState::Leaderboard), cleanup_leaderboard_menu)
+            .add_systems(OnEnter(MenuState::Controls), setup_controls_menu)
+            .add_systems(OnExit(MenuState::Controls), cleanup_controls_menu)
+            .add_systems(Update, (
+                binding_button_action,
+                capture_rebind.run_if(resource_exists::<PendingRebind>),
+                update_binding_text,
+            ).run_if(in_state(MenuState::Controls)).chain())
+            .add_systems(Update, (
+                menu_button_action,
+                difficulty_cycle_action,
+                menu_slider_action,
+                resource_value_text::<MusicVolumeText, MusicVolume>,
+                resource_value_text::<SoundVolumeText, SoundVolume>,
+                update_score_record_text
+            ).run_if(in_state(GameState::MainMenu)))
+            .add_systems(OnExit(GameState::MainMenu), cleanup_main_menu)
+
+            .add_systems(OnEnter(GameState::InGame), setup_hud)
+            .add_systems(Update, (
+                update_player_health_ui,
+                update_score_ui,
+                update_power_up_ui,
+                update_boss_health_ui,
+                update_campaign_level_ui.run_if(resource_exists::<LevelDirector>),
+                resource_value_text::<DifficultyLevelText, DifficultyLevel>,
+                update_ui_padding,
+            ).run_if(in_state(GameState::InGame)).chain())
+            .add_systems(OnEnter(GameplayState::GameOver), handle_game_over)
+            .add_systems(Update, game_over_panel_action.run_if(in_state(GameplayState::GameOver)))
+            .add_systems(Update, pause_on_focus_lost.run_if(in_state(GameplayState::Game)))
+            .add_systems(OnEnter(GameplayState::Paused), setup_pause_menu)
+            .add_systems(Update, (
+                pause_menu_action,
+                menu_slider_action,
+                resource_value_text::<MusicVolumeText, MusicVolume>,
+                resource_value_text::<SoundVolumeText, SoundVolume>,
+            ).run_if(in_state(GameplayState::Paused)))
+            .add_systems(OnExit(GameplayState::Paused), cleanup_pause_menu)
+            .add_systems(OnExit(GameState::InGame), cleanup_hud);
+    }
+}
+
+fn setup_camera(
+    mut commands: Commands
+) {
+    let projection = Projection::Orthographic(OrthographicProjection {
+        scaling_mode: ScalingMode::FixedVertical { viewport_height: 1080.0 },
+        ..OrthographicProjection::default_2d()
+    });
+    commands.spawn((projection, Camera2d));
+}
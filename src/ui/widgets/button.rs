@@ -0,0 +1,123 @@
+use bevy::ecs::relationship::RelatedSpawnerCommands;
+use bevy::input_focus::InputFocus;
+use bevy::prelude::*;
+
+use super::{Focusable, DEFAULT_MARGIN};
+
+pub const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
+pub const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
+pub const PRESSED_BUTTON: Color = Color::srgb(0.35, 0.75, 0.35);
+
+/// A button was clicked, or pressed through focus navigation.
+#[derive(Event, Clone, Copy, PartialEq, Debug)]
+pub struct ButtonPressed {
+    pub entity: Entity,
+}
+
+pub fn press_buttons(
+    interaction_query: Query<(Entity, &Interaction), (Changed<Interaction>, With<Button>)>,
+    mut pressed_writer: EventWriter<ButtonPressed>
+) {
+    for (entity, interaction) in interaction_query {
+        if *interaction == Interaction::Pressed {
+            pressed_writer.write(ButtonPressed { entity });
+        }
+    }
+}
+
+pub fn button_system(
+    interaction_query: Query<(Entity, Ref<Interaction>, &mut BackgroundColor), With<Button>>,
+    focus: Res<InputFocus>
+) {
+    for (entity, interaction, mut background_color) in interaction_query {
+        if !interaction.is_changed() && !focus.is_changed() {
+            continue;
+        }
+        *background_color = match *interaction {
+            Interaction::Pressed => PRESSED_BUTTON.into(),
+            // The focused button looks hovered, so the keyboard and gamepad show where they are.
+            Interaction::Hovered => HOVERED_BUTTON.into(),
+            Interaction::None if focus.0 == Some(entity) => HOVERED_BUTTON.into(),
+            Interaction::None => NORMAL_BUTTON.into(),
+        };
+    }
+}
+
+fn button_node(width: f32, height: f32) -> Node {
+    Node {
+        width: Val::Px(width),
+        height: Val::Px(height),
+        border: UiRect::all(Val::Px(5.0)),
+        justify_content: JustifyContent::Center,
+        align_content: AlignContent::Center,
+        align_items: AlignItems::Center,
+        margin: DEFAULT_MARGIN,
+        ..Default::default()
+    }
+}
+
+pub fn create_button<A: Bundle>(
+    parent: &mut RelatedSpawnerCommands<'_, ChildOf>,
+    width: f32,
+    height: f32,
+    button_text: &str,
+    button_action: A
+) {
+    parent.spawn((
+        Button,
+        button_node(width, height),
+        BorderColor(Color::BLACK),
+        BorderRadius::MAX,
+        BackgroundColor(NORMAL_BUTTON),
+        Focusable,
+        button_action,
+        children![(
+            Text::new(button_text),
+            TextFont {
+                font_size: 33.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+            TextShadow::default()
+        )],
+    ));
+}
+
+/// A button whose label ends with a text span marked by `T`, so the label can show a value.
+/// Toggles and cycles mark it with `ValueText`.
+pub fn create_value_button<T: Component, A: Bundle>(
+    parent: &mut RelatedSpawnerCommands<'_, ChildOf>,
+    width: f32,
+    height: f32,
+    button_text: &str,
+    text_marker: T,
+    button_action: A
+) {
+    parent.spawn((
+        Button,
+        button_node(width, height),
+        BorderColor(Color::BLACK),
+        BorderRadius::MAX,
+        BackgroundColor(NORMAL_BUTTON),
+        Focusable,
+        button_action,
+        children![(
+            Text::new(button_text),
+            TextFont {
+                font_size: 28.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+            TextShadow::default(),
+            children![(
+                TextSpan::default(),
+                TextFont {
+                    font_size: 28.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                text_marker
+            )]
+        )],
+    ));
+}
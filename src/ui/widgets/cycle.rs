@@ -0,0 +1,79 @@
+use bevy::ecs::relationship::RelatedSpawnerCommands;
+use bevy::prelude::*;
+
+use super::{create_value_button, set_value_text, ButtonPressed, ValueText};
+
+/// A button that goes through a list of options. Clicking picks the next one,
+/// Left and Right on a focused cycle go either way.
+#[derive(Component, Clone, PartialEq, Debug)]
+#[require(Button)]
+pub struct Cycle {
+    pub options: Vec<String>,
+    pub index: usize,
+}
+
+#[derive(Event, Clone, Copy, PartialEq, Debug)]
+pub struct CycleChanged {
+    pub entity: Entity,
+    pub index: usize,
+}
+
+impl Cycle {
+    pub fn new(options: impl IntoIterator<Item = impl ToString>, index: usize) -> Self {
+        Cycle {
+            options: options.into_iter().map(|option| option.to_string()).collect(),
+            index,
+        }
+    }
+
+    /// Moves `steps` options forward, or backward when negative, wrapping around.
+    pub fn step_by(&mut self, steps: isize) {
+        if self.options.is_empty() {
+            return;
+        }
+        self.index = (self.index as isize + steps).rem_euclid(self.options.len() as isize) as usize;
+    }
+
+    pub fn selected(&self) -> Option<&str> {
+        self.options.get(self.index).map(String::as_str)
+    }
+}
+
+pub fn advance_cycles(
+    mut pressed_events: EventReader<ButtonPressed>,
+    mut cycles: Query<&mut Cycle>
+) {
+    for event in pressed_events.read() {
+        if let Ok(mut cycle) = cycles.get_mut(event.entity) {
+            cycle.step_by(1);
+        }
+    }
+}
+
+pub fn update_cycles(
+    cycles: Query<(Entity, Ref<Cycle>)>,
+    children: Query<&Children>,
+    mut value_texts: Query<&mut TextSpan, With<ValueText>>,
+    mut changed_writer: EventWriter<CycleChanged>
+) {
+    for (entity, cycle) in cycles {
+        if !cycle.is_changed() {
+            continue;
+        }
+        if !cycle.is_added() {
+            changed_writer.write(CycleChanged { entity, index: cycle.index });
+        }
+        set_value_text(entity, cycle.selected().unwrap_or_default(), &children, &mut value_texts);
+    }
+}
+
+pub fn create_cycle<A: Bundle>(
+    parent: &mut RelatedSpawnerCommands<'_, ChildOf>,
+    width: f32,
+    height: f32,
+    label: &str,
+    cycle: Cycle,
+    cycle_action: A
+) {
+    create_value_button(parent, width, height, label, ValueText, (cycle, cycle_action));
+}
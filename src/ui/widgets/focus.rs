@@ -0,0 +1,86 @@
+use bevy::input_focus::InputFocus;
+use bevy::prelude::*;
+
+use super::{ButtonPressed, Cycle, Slider};
+
+const FOCUS_COLOR: Color = Color::srgb(0.9, 0.75, 0.2);
+
+/// A widget that focus navigation can reach.
+/// The focused one is outlined; the outline stays hidden otherwise.
+#[derive(Component, Default)]
+#[require(Outline = Outline::new(Val::Px(4.0), Val::Px(2.0), Color::NONE))]
+pub struct Focusable;
+
+/// Moves the focus through the widgets on screen with the arrow keys or the gamepad D-pad,
+/// top to bottom and left to right. Left and Right step a focused slider or cycle,
+/// Enter or South presses a focused button.
+pub fn navigate_focus(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    focusables: Query<(Entity, &GlobalTransform), With<Focusable>>,
+    buttons: Query<(), With<Button>>,
+    mut sliders: Query<&mut Slider>,
+    mut cycles: Query<&mut Cycle>,
+    mut focus: ResMut<InputFocus>,
+    mut pressed_writer: EventWriter<ButtonPressed>
+) {
+    let just_pressed = |key, button| keyboard.just_pressed(key) || gamepads.iter().any(|gamepad| gamepad.just_pressed(button));
+    let up = just_pressed(KeyCode::ArrowUp, GamepadButton::DPadUp);
+    let down = just_pressed(KeyCode::ArrowDown, GamepadButton::DPadDown);
+    let left = just_pressed(KeyCode::ArrowLeft, GamepadButton::DPadLeft);
+    let right = just_pressed(KeyCode::ArrowRight, GamepadButton::DPadRight);
+    let activate = just_pressed(KeyCode::Enter, GamepadButton::South) || keyboard.just_pressed(KeyCode::NumpadEnter);
+
+    let mut order: Vec<(Entity, Vec3)> = focusables.iter()
+        .map(|(entity, transform)| (entity, transform.translation()))
+        .collect();
+    // UI space grows downwards.
+    order.sort_by(|(_, a), (_, b)| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+    if order.is_empty() {
+        return;
+    }
+
+    let Some(current) = focus.0.and_then(|focused| order.iter().position(|(entity, _)| *entity == focused)) else {
+        if up || down || left || right || activate {
+            focus.0 = Some(order[0].0);
+        }
+        return;
+    };
+    let focused = order[current].0;
+
+    let mut step = down as isize - up as isize;
+    let sideways = right as i8 - left as i8;
+    if let Ok(mut slider) = sliders.get_mut(focused) {
+        if sideways != 0 {
+            slider.step_by(sideways as i32);
+        }
+    }
+    else if let Ok(mut cycle) = cycles.get_mut(focused) {
+        if sideways != 0 {
+            cycle.step_by(sideways as isize);
+        }
+    }
+    else {
+        step += sideways as isize;
+    }
+    if activate && buttons.contains(focused) {
+        pressed_writer.write(ButtonPressed { entity: focused });
+    }
+
+    if step != 0 {
+        let next = (current as isize + step).rem_euclid(order.len() as isize) as usize;
+        focus.0 = Some(order[next].0);
+    }
+}
+
+pub fn highlight_focus(
+    focus: Res<InputFocus>,
+    outlines: Query<(Entity, &mut Outline), With<Focusable>>
+) {
+    for (entity, mut outline) in outlines {
+        let color = if focus.0 == Some(entity) { FOCUS_COLOR } else { Color::NONE };
+        if outline.color != color {
+            outline.color = color;
+        }
+    }
+}
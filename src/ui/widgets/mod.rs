@@ -0,0 +1,93 @@
+//! Building blocks of the menus. Every widget is a component with an event that reports what
+//! the player did with it, so a screen spawns widgets and reacts to their events.
+
+use bevy::ecs::relationship::RelatedSpawnerCommands;
+use bevy::input_focus::InputFocus;
+use bevy::prelude::*;
+use bevy::ui::UiSystem;
+
+mod button;
+mod cycle;
+mod focus;
+mod slider;
+mod text_input;
+mod toggle;
+pub use button::*;
+pub use cycle::*;
+pub use focus::*;
+pub use slider::*;
+pub use text_input::*;
+pub use toggle::*;
+
+pub const DEFAULT_MARGIN: UiRect = UiRect::all(Val::Px(5.0));
+
+/// Registers the widget events and the systems that drive every widget.
+pub struct WidgetPlugin;
+
+impl Plugin for WidgetPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<InputFocus>()
+            .init_resource::<ButtonInput<MouseButton>>()
+            .add_event::<ButtonPressed>()
+            .add_event::<SliderChanged>()
+            .add_event::<ToggleChanged>()
+            .add_event::<CycleChanged>()
+            .add_event::<TextChanged>()
+
+            // Right after the mouse, so screens see presses in the same frame.
+            .add_systems(PreUpdate, (
+                navigate_focus.in_set(FocusNavigation),
+                press_buttons,
+            ).after(UiSystem::Focus))
+            .add_systems(Update, (
+                button_system,
+                highlight_focus.run_if(resource_changed::<InputFocus>),
+                (slider_system, update_slider_bars).chain(),
+                (flip_toggles, update_toggles).chain(),
+                (advance_cycles, update_cycles).chain(),
+                (type_text, update_text_inputs).chain(),
+            ));
+    }
+}
+
+/// Focus navigation with the keyboard and gamepad. Screens that need those keys for
+/// something else can add a run condition to the set.
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct FocusNavigation;
+
+/// The text span at the end of a widget label that shows the widget's value.
+#[derive(Component)]
+pub struct ValueText;
+
+pub fn create_text(
+    parent: &mut RelatedSpawnerCommands<'_, ChildOf>,
+    size: f32,
+    text: &str
+) {
+    parent.spawn((
+        Node {
+            margin: DEFAULT_MARGIN,
+            ..Default::default()
+        },
+        Text::new(text),
+        TextFont {
+            font_size: size,
+            ..Default::default()
+        }
+    ));
+}
+
+// Writes `value` into the `ValueText` span of the widget label.
+fn set_value_text(
+    widget: Entity,
+    value: &str,
+    children: &Query<&Children>,
+    value_texts: &mut Query<&mut TextSpan, With<ValueText>>
+) {
+    for descendant in children.iter_descendants(widget) {
+        if let Ok(mut text) = value_texts.get_mut(descendant) {
+            **text = value.to_string();
+        }
+    }
+}
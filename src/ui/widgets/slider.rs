@@ -0,0 +1,176 @@
+use bevy::ecs::observer::TriggerTargets;
+use bevy::ecs::relationship::RelatedSpawnerCommands;
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+
+use super::Focusable;
+
+/// Share of the range that one keyboard step moves a slider without steps.
+pub const SLIDER_KEYBOARD_STEP: f32 = 0.05;
+
+#[derive(Component, Default)]
+pub struct SliderBar;
+
+/// A bar the player drags to pick a value between `min` and `max`.
+/// `value` is normalized to `0..=1`; a non-zero `step` snaps it to multiples of the step.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+#[require(Node, Interaction, RelativeCursorPosition)]
+pub struct Slider {
+    pub min: f32,
+    pub max: f32,
+    pub value: f32,
+    pub step: f32
+}
+
+/// The value of a slider changed. `value` is in the slider's own range.
+#[derive(Event, Clone, Copy, PartialEq, Debug)]
+pub struct SliderChanged {
+    pub entity: Entity,
+    pub value: f32,
+}
+
+impl Slider {
+    pub fn new(min: f32, max: f32, value: f32) -> Self {
+        let mut slider = Slider { min, max, value: 0.0, step: 0.0 };
+        slider.set_absolute_value(value);
+        slider
+    }
+
+    pub fn with_step(mut self, step: f32) -> Self {
+        self.step = step;
+        let value = self.value;
+        self.set_value(value);
+        self
+    }
+
+    pub fn set_value(&mut self, x: f32) {
+        let mut value = f32::clamp(x, 0.0, 1.0);
+        let range = self.max - self.min;
+        if self.step > 0.0 && range > 0.0 {
+            let steps = (value * range / self.step).round();
+            value = f32::clamp(steps * self.step / range, 0.0, 1.0);
+        }
+        self.value = value;
+    }
+
+    pub fn get_absolute_value(&self) -> f32 {
+        let range = self.max - self.min;
+        if self.step > 0.0 && range > 0.0 {
+            // Whole steps, without the rounding error of the normalized value.
+            return self.min + (self.value * range / self.step).round() * self.step;
+        }
+        self.min + range * self.value
+    }
+
+    pub fn set_absolute_value(&mut self, value: f32) {
+        let range = self.max - self.min;
+        let normalized = if range > 0.0 { (value - self.min) / range } else { 0.0 };
+        self.set_value(normalized);
+    }
+
+    /// Moves the slider by `steps` of its step, or by `SLIDER_KEYBOARD_STEP` of its range without one.
+    pub fn step_by(&mut self, steps: i32) {
+        let range = self.max - self.min;
+        let step = if self.step > 0.0 && range > 0.0 { self.step / range } else { SLIDER_KEYBOARD_STEP };
+        let value = self.value + steps as f32 * step;
+        self.set_value(value);
+    }
+}
+
+impl Default for Slider {
+    fn default() -> Self {
+        Slider { min: 0.0, max: 1.0, value: 0.0, step: 0.0 }
+    }
+}
+
+/// The slider being dragged. It follows the cursor until the mouse button is released,
+/// even when the cursor leaves the bar.
+#[derive(Component)]
+pub struct SliderDrag;
+
+pub fn slider_system(
+    mouse: Res<ButtonInput<MouseButton>>,
+    sliders: Query<(Entity, &RelativeCursorPosition, &Interaction, &mut Slider, Has<SliderDrag>)>,
+    mut commands: Commands
+) {
+    for (entity, pos, interaction, mut slider, dragging) in sliders {
+        let held = *interaction == Interaction::Pressed || (dragging && mouse.pressed(MouseButton::Left));
+        if held && !dragging {
+            commands.entity(entity).insert(SliderDrag);
+        }
+        else if !held && dragging {
+            commands.entity(entity).remove::<SliderDrag>();
+        }
+
+        // Outside the window the cursor has no position, so the slider keeps its value.
+        if held
+            && let Some(vec) = pos.normalized
+        {
+            let mut dragged = *slider;
+            dragged.set_value(vec.x);
+            slider.set_if_neq(dragged);
+        }
+    }
+}
+
+// Sliders also change by focus navigation, so the bar follows the value rather than the cursor.
+pub fn update_slider_bars(
+    sliders: Query<(Entity, Ref<Slider>, Option<&Children>)>,
+    mut slider_bars: Query<&mut Node, With<SliderBar>>,
+    mut changed_writer: EventWriter<SliderChanged>
+) {
+    for (entity, slider, children) in sliders {
+        if !slider.is_changed() {
+            continue;
+        }
+        if !slider.is_added() {
+            changed_writer.write(SliderChanged { entity, value: slider.get_absolute_value() });
+        }
+        for child in children.into_iter().flat_map(|children| children.entities()) {
+            if let Ok(mut node) = slider_bars.get_mut(child) {
+                node.width = Val::Percent(slider.value * 100.0);
+            }
+        }
+    }
+}
+
+pub fn create_slider<A: Bundle>(
+    parent: &mut RelatedSpawnerCommands<'_, ChildOf>,
+    width: f32,
+    height: f32,
+    slider: Slider,
+    slider_action: A
+) {
+    parent.spawn((
+        Node {
+            width: Val::Px(width),
+            height: Val::Px(height),
+            padding: UiRect::all(Val::Px(5.0)),
+            ..Default::default()
+        },
+        BackgroundColor(Color::BLACK)
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..Default::default()
+            },
+            slider,
+            Focusable,
+            slider_action
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Node {
+                    width: Val::Percent(slider.value * 100.0),
+                    height: Val::Percent(100.0),
+                    ..Default::default()
+                },
+                BackgroundColor(Color::WHITE),
+                SliderBar
+            ));
+        });
+    });
+}
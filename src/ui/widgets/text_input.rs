@@ -0,0 +1,104 @@
+use bevy::ecs::relationship::RelatedSpawnerCommands;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input_focus::InputFocus;
+use bevy::prelude::*;
+
+use super::{Focusable, DEFAULT_MARGIN};
+
+/// A line of text typed by the player while it has the focus. `filter` turns every typed
+/// character into the one to add, or drops it.
+#[derive(Component, Clone, Debug)]
+#[require(Text, Focusable)]
+pub struct TextInput {
+    pub value: String,
+    pub max_length: usize,
+    pub filter: fn(char) -> Option<char>,
+}
+
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct TextChanged {
+    pub entity: Entity,
+    pub value: String,
+}
+
+impl TextInput {
+    pub fn new(max_length: usize) -> Self {
+        TextInput { value: String::new(), max_length, filter: Some }
+    }
+
+    pub fn with_filter(mut self, filter: fn(char) -> Option<char>) -> Self {
+        self.filter = filter;
+        self
+    }
+}
+
+pub fn type_text(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    focus: Res<InputFocus>,
+    mut inputs: Query<&mut TextInput>
+) {
+    let Some(mut input) = focus.0.and_then(|focused| inputs.get_mut(focused).ok()) else {
+        keyboard_events.clear();
+        return;
+    };
+
+    for event in keyboard_events.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Backspace => {
+                input.value.pop();
+            }
+            Key::Character(typed) => {
+                for character in typed.chars().filter_map(input.filter) {
+                    if input.value.chars().count() < input.max_length {
+                        input.value.push(character);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+// Empty places of the input are shown as underscores.
+pub fn update_text_inputs(
+    inputs: Query<(Entity, Ref<TextInput>, &mut Text)>,
+    mut focus: ResMut<InputFocus>,
+    mut changed_writer: EventWriter<TextChanged>
+) {
+    for (entity, input, mut text) in inputs {
+        if !input.is_changed() {
+            continue;
+        }
+        if input.is_added() {
+            // A new input is there to be typed in.
+            focus.0 = Some(entity);
+        }
+        else {
+            changed_writer.write(TextChanged { entity, value: input.value.clone() });
+        }
+        text.0 = format!("{:_<width$}", input.value, width = input.max_length);
+    }
+}
+
+pub fn create_text_input<A: Bundle>(
+    parent: &mut RelatedSpawnerCommands<'_, ChildOf>,
+    size: f32,
+    input: TextInput,
+    input_action: A
+) {
+    parent.spawn((
+        Node {
+            margin: DEFAULT_MARGIN,
+            ..Default::default()
+        },
+        TextFont {
+            font_size: size,
+            ..Default::default()
+        },
+        input,
+        input_action
+    ));
+}
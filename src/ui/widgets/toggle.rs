@@ -0,0 +1,56 @@
+use bevy::ecs::relationship::RelatedSpawnerCommands;
+use bevy::prelude::*;
+
+use super::{create_value_button, set_value_text, ButtonPressed, ValueText};
+
+/// A button that switches a setting on and off.
+#[derive(Component, Clone, Copy, PartialEq, Debug, Default)]
+#[require(Button)]
+pub struct Toggle {
+    pub on: bool,
+}
+
+#[derive(Event, Clone, Copy, PartialEq, Debug)]
+pub struct ToggleChanged {
+    pub entity: Entity,
+    pub on: bool,
+}
+
+pub fn flip_toggles(
+    mut pressed_events: EventReader<ButtonPressed>,
+    mut toggles: Query<&mut Toggle>
+) {
+    for event in pressed_events.read() {
+        if let Ok(mut toggle) = toggles.get_mut(event.entity) {
+            toggle.on = !toggle.on;
+        }
+    }
+}
+
+pub fn update_toggles(
+    toggles: Query<(Entity, Ref<Toggle>)>,
+    children: Query<&Children>,
+    mut value_texts: Query<&mut TextSpan, With<ValueText>>,
+    mut changed_writer: EventWriter<ToggleChanged>
+) {
+    for (entity, toggle) in toggles {
+        if !toggle.is_changed() {
+            continue;
+        }
+        if !toggle.is_added() {
+            changed_writer.write(ToggleChanged { entity, on: toggle.on });
+        }
+        set_value_text(entity, if toggle.on { "On" } else { "Off" }, &children, &mut value_texts);
+    }
+}
+
+pub fn create_toggle<A: Bundle>(
+    parent: &mut RelatedSpawnerCommands<'_, ChildOf>,
+    width: f32,
+    height: f32,
+    label: &str,
+    on: bool,
+    toggle_action: A
+) {
+    create_value_button(parent, width, height, label, ValueText, (Toggle { on }, toggle_action));
+}
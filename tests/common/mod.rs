@@ -0,0 +1,230 @@
+//! Headless runs of the gameplay for tests: no window, audio or GPU.
+// Every test file compiles its own copy and uses only some of the helpers.
+#![allow(dead_code)]
+
+use bevy::asset::AssetPlugin;
+use bevy::input::gamepad::{
+    gamepad_connection_system, gamepad_event_processing_system, GamepadAxisChangedEvent, GamepadButtonChangedEvent,
+    GamepadButtonStateChangedEvent, GamepadConnection, GamepadConnectionEvent, GamepadEvent, RawGamepadAxisChangedEvent,
+    RawGamepadButtonChangedEvent, RawGamepadEvent,
+};
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimeUpdateStrategy;
+
+use rust_game::audio::SoundVolume;
+use rust_game::gameplay::*;
+use rust_game::GameState;
+
+pub const SEED: u64 = 42;
+
+/// A run of the game driven by a test. Every `step` is one frame that runs exactly one
+/// simulation tick. Asteroids, enemies and bosses only appear when the test spawns them.
+pub struct Scenario {
+    pub app: App,
+    gamepad: Option<Entity>,
+}
+
+impl Scenario {
+    pub fn new() -> Self {
+        Scenario::with_preset(DifficultyPreset::Normal)
+    }
+
+    pub fn with_preset(preset: DifficultyPreset) -> Self {
+        let mut app = App::new();
+        app
+            .add_plugins((
+                MinimalPlugins,
+                AssetPlugin { watch_for_changes_override: Some(false), ..default() },
+                StatesPlugin,
+                GameplayPlugin,
+            ))
+            .init_asset::<Image>()
+            .init_asset::<AudioSource>()
+            .insert_resource(RequestedSeed(Some(SEED)))
+            .insert_resource(preset)
+            .insert_resource(ButtonInput::<KeyCode>::default())
+            .insert_resource(SoundVolume(0.0))
+            .insert_resource(LazerShootingSound(Handle::default()))
+            .insert_resource(DamageSound(Handle::default()))
+            .insert_resource(PowerUpSound(Handle::default()))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Time::<Fixed>::default().timestep()))
+            .insert_state(GameState::InGame);
+        add_gamepad_input(&mut app);
+
+        let mut scenario = Scenario { app, gamepad: None };
+        // Enter the run and spawn the player.
+        scenario.app.update();
+        scenario.app.update();
+        // Tests use the default tuning, not the file in `assets`.
+        scenario.world().remove_resource::<GameTuningHandle>();
+        scenario.stop_spawning();
+        scenario
+    }
+
+    pub fn world(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+
+    fn stop_spawning(&mut self) {
+        self.world().resource_mut::<AsteroidSpawTimer>().0.pause();
+        self.world().resource_mut::<EnemySpawnTimer>().0.pause();
+        self.world().resource_mut::<BossEncounter>().next_score = i32::MAX;
+    }
+
+    pub fn player_at(&mut self, x: f32) -> &mut Self {
+        let mut player = self.world().query_filtered::<(&mut Transform, &mut Interpolated), With<Player>>();
+        let (mut transform, mut interpolated) = player.single_mut(self.world()).unwrap();
+        transform.translation.x = x;
+        // Otherwise the last simulated position is restored before the next tick.
+        *interpolated = Interpolated::default();
+        self
+    }
+
+    pub fn player_x(&mut self) -> f32 {
+        let mut player = self.world().query_filtered::<&Transform, With<Player>>();
+        player.single(self.world()).unwrap().translation.x
+    }
+
+    /// Spawns an asteroid that falls at the speed of its size.
+    pub fn asteroid(&mut self, size: AsteroidSize, position: Vec2) -> &mut Self {
+        let asset_server = self.world().resource::<AssetServer>().clone();
+        let tuning = self.world().resource::<GameTuning>().clone();
+        let mut commands = self.world().commands();
+        spawn_asteroid_of_size(&mut commands, &asset_server, &tuning, size, Transform::from_translation(position.extend(0.0)), Vec2::NEG_Y, 1.0);
+        self.world().flush();
+        self
+    }
+
+    pub fn boss(&mut self, x: f32) -> &mut Self {
+        let asset_server = self.world().resource::<AssetServer>().clone();
+        let tuning = self.world().resource::<GameTuning>().clone();
+        let mut commands = self.world().commands();
+        spawn_boss_ship(&mut commands, &asset_server, &tuning, x);
+        self.world().flush();
+        self
+    }
+
+    pub fn asteroid_count(&mut self) -> usize {
+        let mut asteroids = self.world().query_filtered::<(), With<Asteroid>>();
+        asteroids.iter(self.world()).count()
+    }
+
+    pub fn hold(&mut self, key: KeyCode) -> &mut Self {
+        self.world().resource_mut::<ButtonInput<KeyCode>>().press(key);
+        self
+    }
+
+    pub fn release(&mut self, key: KeyCode) -> &mut Self {
+        self.world().resource_mut::<ButtonInput<KeyCode>>().release(key);
+        self
+    }
+
+    /// Presses and releases the key within the next frame.
+    pub fn tap(&mut self, key: KeyCode) -> &mut Self {
+        self.hold(key).step(1).release(key)
+    }
+
+    /// Connects the gamepad that `stick` and the gamepad button methods drive.
+    pub fn connect_gamepad(&mut self) -> &mut Self {
+        let gamepad = self.world().spawn_empty().id();
+        let connection = GamepadConnection::Connected { name: "Test gamepad".to_string(), vendor_id: None, product_id: None };
+        // Sent directly like the gamepad backend does, the raw event only reaches `GamepadEvent` readers.
+        self.world().send_event(GamepadConnectionEvent::new(gamepad, connection));
+        self.gamepad = Some(gamepad);
+        self.step(1)
+    }
+
+    /// Moves the left stick sideways, from -1.0 for full left to 1.0 for full right.
+    pub fn stick(&mut self, x: f32) -> &mut Self {
+        let gamepad = self.gamepad.expect("no gamepad is connected");
+        self.world().send_event(RawGamepadEvent::Axis(RawGamepadAxisChangedEvent::new(gamepad, GamepadAxis::LeftStickX, x)));
+        self
+    }
+
+    pub fn hold_button(&mut self, button: GamepadButton) -> &mut Self {
+        let gamepad = self.gamepad.expect("no gamepad is connected");
+        self.world().send_event(RawGamepadEvent::Button(RawGamepadButtonChangedEvent::new(gamepad, button, 1.0)));
+        self
+    }
+
+    pub fn release_button(&mut self, button: GamepadButton) -> &mut Self {
+        let gamepad = self.gamepad.expect("no gamepad is connected");
+        self.world().send_event(RawGamepadEvent::Button(RawGamepadButtonChangedEvent::new(gamepad, button, 0.0)));
+        self
+    }
+
+    /// Presses and releases the button within the next frame.
+    pub fn tap_button(&mut self, button: GamepadButton) -> &mut Self {
+        self.hold_button(button).step(1).release_button(button)
+    }
+
+    /// Turns the run into a campaign run of the given campaign.
+    pub fn campaign(&mut self, campaign: Handle<Campaign>) -> &mut Self {
+        self.world().insert_resource(CampaignHandle(campaign));
+        self.world().insert_resource(LevelDirector::default());
+        self
+    }
+
+    /// Steps until the run is over. Assets load in the background, so this gives the loader
+    /// some real time too.
+    pub fn wait_for_game_over(&mut self) -> &mut Self {
+        for _ in 0..200 {
+            if self.state() == GameplayState::GameOver {
+                return self;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            self.step(1);
+        }
+        panic!("the run didn't end");
+    }
+
+    pub fn restart(&mut self) -> &mut Self {
+        self.world().send_event_default::<RestartEvent>();
+        self.step(2);
+        // The new run starts with fresh timers.
+        self.stop_spawning();
+        self
+    }
+
+    pub fn step(&mut self, frames: u32) -> &mut Self {
+        for _ in 0..frames {
+            self.app.update();
+            self.world().resource_mut::<ButtonInput<KeyCode>>().clear();
+        }
+        self
+    }
+
+    pub fn score(&mut self) -> i32 {
+        self.world().resource::<Score>().0
+    }
+
+    pub fn health(&mut self) -> i32 {
+        let mut player = self.world().query_filtered::<&Health, With<Player>>();
+        player.single(self.world()).unwrap().0
+    }
+
+    pub fn state(&mut self) -> GameplayState {
+        *self.world().resource::<State<GameplayState>>().get()
+    }
+}
+
+// The gamepad part of `InputPlugin`. The keyboard part would clear the keys a test presses
+// before the game sees them, so tests press keys on `ButtonInput` directly instead.
+fn add_gamepad_input(app: &mut App) {
+    app
+        .add_event::<GamepadEvent>()
+        .add_event::<GamepadConnectionEvent>()
+        .add_event::<GamepadButtonChangedEvent>()
+        .add_event::<GamepadButtonStateChangedEvent>()
+        .add_event::<GamepadAxisChangedEvent>()
+        .add_event::<RawGamepadEvent>()
+        .add_event::<RawGamepadAxisChangedEvent>()
+        .add_event::<RawGamepadButtonChangedEvent>()
+        .add_systems(PreUpdate, (gamepad_connection_system, gamepad_event_processing_system).chain());
+}
+
+/// Number of steps that simulate this many seconds.
+pub fn seconds(seconds: f32) -> u32 {
+    (seconds / Time::<Fixed>::default().timestep().as_secs_f32()).ceil() as u32
+}
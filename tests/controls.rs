@@ -0,0 +1,88 @@
+mod common;
+
+use bevy::prelude::*;
+use common::{seconds, Scenario};
+use rust_game::database::{MemoryStore, Storage};
+use rust_game::gameplay::{Action, Bindings, Lazer};
+
+fn lazer_count(scenario: &mut Scenario) -> usize {
+    let mut lazers = scenario.world().query_filtered::<(), With<Lazer>>();
+    lazers.iter(scenario.world()).count()
+}
+
+#[test]
+fn arrow_keys_move_the_player_by_default() {
+    let mut scenario = Scenario::new();
+
+    scenario.hold(KeyCode::ArrowLeft).step(seconds(3.0));
+
+    assert_eq!(scenario.player_x(), -200.0);
+}
+
+#[test]
+fn rebound_fire_key_replaces_the_old_one() {
+    let mut scenario = Scenario::new();
+    scenario.world().resource_mut::<Bindings>().bind(Action::Fire, 0, KeyCode::KeyF);
+    scenario.step(seconds(0.5));
+
+    scenario.tap(KeyCode::Space).step(1);
+    assert_eq!(lazer_count(&mut scenario), 0);
+
+    scenario.tap(KeyCode::KeyF).step(1);
+    assert_eq!(lazer_count(&mut scenario), 1);
+}
+
+#[test]
+fn key_is_bound_to_one_action_at_most() {
+    let mut bindings = Bindings::default();
+
+    bindings.bind(Action::Fire, 1, KeyCode::KeyA);
+
+    assert_eq!(bindings.keys(Action::MoveLeft).collect::<Vec<_>>(), [KeyCode::ArrowLeft]);
+    assert_eq!(bindings.keys(Action::Fire).collect::<Vec<_>>(), [KeyCode::Space, KeyCode::KeyA]);
+}
+
+#[test]
+fn bindings_survive_saving() {
+    let mut bindings = Bindings::default();
+    bindings.bind(Action::MoveLeft, 0, KeyCode::KeyJ);
+    bindings.clear(Action::Pause, 1);
+    let mut storage = Storage::new(MemoryStore::default());
+
+    storage.update(|data| data.bindings = bindings.to_saved());
+
+    assert_eq!(Bindings::from_saved(&storage.load().bindings), bindings);
+}
+
+#[test]
+fn stick_moves_the_player_as_far_as_it_is_pushed() {
+    let mut scenario = Scenario::new();
+    scenario.connect_gamepad();
+
+    scenario.stick(0.5).step(seconds(0.5));
+
+    // Half the player speed of 250 for half a second.
+    assert!((scenario.player_x() - 62.5).abs() < 3.0, "player is at {}", scenario.player_x());
+}
+
+#[test]
+fn resting_stick_does_not_move_the_player() {
+    let mut scenario = Scenario::new();
+    scenario.connect_gamepad();
+
+    scenario.stick(0.1).step(seconds(0.5));
+
+    assert_eq!(scenario.player_x(), 0.0);
+}
+
+#[test]
+fn gamepad_fires_and_moves_with_the_d_pad() {
+    let mut scenario = Scenario::new();
+    scenario.connect_gamepad().step(seconds(0.5));
+
+    scenario.tap_button(GamepadButton::South).step(1);
+    assert_eq!(lazer_count(&mut scenario), 1);
+
+    scenario.hold_button(GamepadButton::DPadRight).step(seconds(3.0));
+    assert_eq!(scenario.player_x(), 200.0);
+}
@@ -0,0 +1,214 @@
+mod common;
+
+use bevy::prelude::*;
+use common::{seconds, Scenario};
+use rust_game::gameplay::{AsteroidSize, AsteroidSpawTimer, Boss, BossEncounter, Campaign, Level, Dead, Direction, DifficultyPreset, GameplayState, LevelDirector, Lazer, PLAYER_SPAWN_HEIGHT};
+
+#[test]
+fn run_starts_with_full_health_and_no_score() {
+    let mut scenario = Scenario::new();
+
+    assert_eq!(scenario.state(), GameplayState::Game);
+    assert_eq!(scenario.health(), 3);
+    assert_eq!(scenario.score(), 0);
+}
+
+#[test]
+fn player_stays_inside_the_playfield() {
+    let mut scenario = Scenario::new();
+
+    scenario.hold(KeyCode::KeyD).step(seconds(3.0));
+
+    assert_eq!(scenario.player_x(), 200.0);
+}
+
+#[test]
+fn laser_destroys_asteroid_and_scores() {
+    let mut scenario = Scenario::new();
+    scenario.step(seconds(0.5)).asteroid(AsteroidSize::Small, Vec2::new(0.0, 0.0));
+
+    scenario.tap(KeyCode::Space).step(seconds(0.5));
+
+    assert_eq!(scenario.asteroid_count(), 0);
+    assert_eq!(scenario.score(), 2);
+    assert_eq!(scenario.health(), 3);
+}
+
+#[test]
+fn destroyed_big_asteroid_breaks_into_fragments() {
+    let mut scenario = Scenario::new();
+    scenario.step(seconds(0.5)).asteroid(AsteroidSize::Big, Vec2::new(0.0, 100.0));
+
+    // A big asteroid takes two hits.
+    scenario.tap(KeyCode::Space).step(seconds(0.5));
+    scenario.tap(KeyCode::Space).step(seconds(0.35));
+
+    assert_eq!(scenario.asteroid_count(), 2);
+    assert_eq!(scenario.score(), 5);
+}
+
+#[test]
+fn asteroid_hitting_the_player_takes_health() {
+    let mut scenario = Scenario::new();
+    scenario.player_at(-150.0).asteroid(AsteroidSize::Medium, Vec2::new(-150.0, PLAYER_SPAWN_HEIGHT + 150.0));
+
+    scenario.step(seconds(0.5));
+
+    assert_eq!(scenario.health(), 2);
+    assert_eq!(scenario.asteroid_count(), 0);
+    assert_eq!(scenario.score(), 0);
+}
+
+#[test]
+fn asteroid_reaching_the_bottom_takes_health() {
+    let mut scenario = Scenario::new();
+    scenario.asteroid(AsteroidSize::Small, Vec2::new(200.0, -450.0));
+
+    scenario.step(seconds(0.5));
+
+    assert_eq!(scenario.health(), 2);
+    assert_eq!(scenario.asteroid_count(), 0);
+}
+
+#[test]
+fn lasers_leaving_the_screen_are_removed() {
+    let mut scenario = Scenario::new();
+    for _ in 0..3 {
+        scenario.step(seconds(0.5)).tap(KeyCode::Space);
+    }
+
+    scenario.step(seconds(2.0));
+
+    let mut lazers = scenario.world().query_filtered::<(), With<Lazer>>();
+    assert_eq!(lazers.iter(scenario.world()).count(), 0);
+}
+
+#[test]
+fn losing_all_health_ends_the_run() {
+    let mut scenario = Scenario::with_preset(DifficultyPreset::Nightmare);
+    scenario.asteroid(AsteroidSize::Small, Vec2::new(200.0, -450.0));
+
+    scenario.step(seconds(0.5));
+
+    assert_eq!(scenario.state(), GameplayState::GameOver);
+}
+
+#[test]
+fn restart_resets_the_run() {
+    let mut scenario = Scenario::with_preset(DifficultyPreset::Nightmare);
+    scenario.step(seconds(0.5)).asteroid(AsteroidSize::Small, Vec2::new(0.0, 0.0));
+    scenario.tap(KeyCode::Space).step(seconds(0.5));
+    scenario.asteroid(AsteroidSize::Small, Vec2::new(200.0, -450.0)).step(seconds(0.5));
+    assert_eq!(scenario.state(), GameplayState::GameOver);
+    assert!(scenario.score() > 0);
+
+    scenario.restart();
+
+    assert_eq!(scenario.state(), GameplayState::Game);
+    assert_eq!(scenario.score(), 0);
+    assert_eq!(scenario.health(), 1);
+}
+
+#[test]
+fn paused_run_stands_still_until_resumed() {
+    let mut scenario = Scenario::new();
+
+    // The state changes on the frame after the key press.
+    scenario.tap(KeyCode::Escape).step(1);
+    assert_eq!(scenario.state(), GameplayState::Paused);
+    scenario.hold(KeyCode::KeyD).step(seconds(1.0));
+    assert_eq!(scenario.player_x(), 0.0);
+
+    scenario.release(KeyCode::KeyD).tap(KeyCode::KeyP).step(1);
+    assert_eq!(scenario.state(), GameplayState::Game);
+    scenario.hold(KeyCode::KeyD).step(seconds(0.5));
+    assert!(scenario.player_x() > 0.0);
+    // Resuming keeps the same player.
+    assert_eq!(scenario.health(), 3);
+}
+
+#[test]
+fn restart_from_pause_starts_a_new_run() {
+    let mut scenario = Scenario::new();
+    scenario.step(seconds(0.5)).asteroid(AsteroidSize::Small, Vec2::new(0.0, 0.0));
+    scenario.tap(KeyCode::Space).step(seconds(0.5));
+    scenario.player_at(-150.0).tap(KeyCode::Escape).step(1);
+    assert_eq!(scenario.state(), GameplayState::Paused);
+
+    scenario.restart();
+
+    assert_eq!(scenario.state(), GameplayState::Game);
+    assert_eq!(scenario.score(), 0);
+    assert_eq!(scenario.player_x(), 0.0);
+}
+
+#[test]
+fn restart_starts_the_spawn_timers_over() {
+    let mut scenario = Scenario::new();
+    scenario.world().resource_mut::<AsteroidSpawTimer>().0.unpause();
+    scenario.step(seconds(1.0)).tap(KeyCode::Escape).step(1);
+    assert!(scenario.world().resource::<AsteroidSpawTimer>().0.elapsed_secs() > 0.5);
+
+    scenario.restart();
+
+    assert!(scenario.world().resource::<AsteroidSpawTimer>().0.elapsed_secs() < 0.1);
+}
+
+#[test]
+fn campaign_that_fails_to_load_ends_the_run() {
+    let mut scenario = Scenario::new();
+    let missing = scenario.world().resource::<AssetServer>().load("missing.campaign.ron");
+
+    scenario.campaign(missing).wait_for_game_over();
+
+    assert!(scenario.world().resource::<LevelDirector>().load_failed);
+}
+
+#[test]
+fn level_that_fails_to_load_ends_the_run() {
+    let mut scenario = Scenario::new();
+    let missing = scenario.world().resource::<AssetServer>().load("levels/missing.level.ron");
+    let campaign = scenario.world().resource_mut::<Assets<Campaign>>().add(Campaign { levels: vec![missing] });
+
+    scenario.campaign(campaign).wait_for_game_over();
+
+    assert!(scenario.world().resource::<LevelDirector>().load_failed);
+}
+
+#[test]
+fn two_bosses_fight_and_fall_together() {
+    let mut scenario = Scenario::new();
+    scenario.boss(-150.0).boss(150.0).step(seconds(4.0));
+
+    let mut bosses = scenario.world().query_filtered::<(Entity, &Direction), With<Boss>>();
+    let bosses: Vec<(Entity, f32)> = bosses.iter(scenario.world()).map(|(entity, direction)| (entity, direction.y)).collect();
+    assert_eq!(bosses.len(), 2);
+    // Both came down and now move from side to side.
+    assert!(bosses.iter().all(|(_, down)| *down == 0.0));
+
+    // Killed on the same tick.
+    for (boss, _) in bosses {
+        scenario.world().entity_mut(boss).insert(Dead);
+    }
+    scenario.step(2);
+
+    let mut remaining = scenario.world().query_filtered::<(), With<Boss>>();
+    assert_eq!(remaining.iter(scenario.world()).count(), 0);
+    assert_eq!(scenario.world().resource::<BossEncounter>().defeated, 2);
+}
+
+#[test]
+fn campaign_run_waits_for_its_levels() {
+    let mut scenario = Scenario::new();
+    let level = scenario.world().resource::<Assets<Level>>().reserve_handle();
+    let campaign = scenario.world().resource_mut::<Assets<Campaign>>().add(Campaign { levels: vec![level.clone()] });
+    scenario.campaign(campaign);
+
+    scenario.hold(KeyCode::KeyD).step(seconds(0.5));
+    assert_eq!(scenario.player_x(), 0.0);
+
+    let empty = Level { name: "Empty".to_string(), waves: Vec::new() };
+    scenario.world().resource_mut::<Assets<Level>>().insert(&level, empty);
+    scenario.step(seconds(0.5));
+    assert!(scenario.player_x() > 0.0);
+}
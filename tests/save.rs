@@ -0,0 +1,52 @@
+use std::sync::{Arc, Mutex};
+
+use rust_game::database::{MemoryStore, SaveData, SaveError, SaveStore, Storage, DEFAULT_DIFFICULTY};
+
+/// A store the test can still look into after handing it to `Storage`.
+#[derive(Clone, Default)]
+struct SharedStore(Arc<Mutex<MemoryStore>>);
+
+impl SaveStore for SharedStore {
+    fn load(&self) -> Result<SaveData, SaveError> {
+        self.0.lock().unwrap().load()
+    }
+
+    fn save(&mut self, data: &SaveData) -> Result<(), SaveError> {
+        self.0.lock().unwrap().save(data)
+    }
+}
+
+#[test]
+fn legacy_save_is_migrated_on_the_next_update() {
+    let mut legacy = Vec::new();
+    legacy.extend_from_slice(&1500i32.to_ne_bytes());
+    legacy.extend_from_slice(&25.0f32.to_ne_bytes());
+    let mut storage = Storage::new(MemoryStore::from_bytes(legacy));
+
+    assert_eq!(storage.load().records.get(DEFAULT_DIFFICULTY), Some(&1500));
+    assert_eq!(storage.load().sound_volume, 25.0);
+
+    storage.update(|data| data.music_volume = 50.0);
+
+    let data = storage.load();
+    assert_eq!(data.records.get(DEFAULT_DIFFICULTY), Some(&1500));
+    assert_eq!((data.sound_volume, data.music_volume), (25.0, 50.0));
+}
+
+#[test]
+fn unreadable_save_loads_the_defaults() {
+    let storage = Storage::new(MemoryStore::from_bytes(b"not a save file at all".to_vec()));
+
+    assert_eq!(storage.load(), Default::default());
+}
+
+#[test]
+fn unreadable_save_is_not_overwritten() {
+    let bytes = b"not a save file at all".to_vec();
+    let store = SharedStore(Arc::new(Mutex::new(MemoryStore::from_bytes(bytes.clone()))));
+    let mut storage = Storage::new(store.clone());
+
+    storage.update(|data| data.music_volume = 10.0);
+
+    assert_eq!(store.0.lock().unwrap().bytes(), Some(&bytes[..]));
+}
@@ -0,0 +1,206 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::input_focus::InputFocus;
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+use rust_game::ui::widgets::*;
+
+/// Widgets stacked top to bottom, driven by the keyboard.
+struct Menu {
+    app: App,
+    widgets: Vec<Entity>,
+}
+
+impl Menu {
+    fn new() -> Self {
+        let mut app = App::new();
+        app
+            .init_resource::<ButtonInput<KeyCode>>()
+            .add_event::<KeyboardInput>()
+            .add_plugins(WidgetPlugin);
+        Menu { app, widgets: Vec::new() }
+    }
+
+    fn with_buttons(buttons: usize) -> Self {
+        let mut menu = Menu::new();
+        // Spawned bottom to top, so the order on screen isn't the spawn order.
+        let mut buttons: Vec<Entity> = (0..buttons).rev()
+            .map(|row| menu.app.world_mut().spawn((Button, Focusable, GlobalTransform::from_xyz(0.0, row as f32 * 100.0, 0.0))).id())
+            .collect();
+        buttons.reverse();
+        menu.widgets = buttons;
+        menu
+    }
+
+    /// Adds a widget below the others.
+    fn add(&mut self, widget: impl Bundle) -> Entity {
+        let row = self.widgets.len() as f32;
+        let entity = self.app.world_mut().spawn((widget, GlobalTransform::from_xyz(0.0, row * 100.0, 0.0))).id();
+        self.widgets.push(entity);
+        entity
+    }
+
+    fn tap(&mut self, key: KeyCode) -> &mut Self {
+        self.app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(key);
+        self.app.update();
+        let mut keyboard = self.app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keyboard.release(key);
+        keyboard.clear();
+        self
+    }
+
+    fn type_text(&mut self, text: &str) -> &mut Self {
+        for character in text.chars() {
+            self.app.world_mut().send_event(KeyboardInput {
+                key_code: KeyCode::Unidentified(bevy::input::keyboard::NativeKeyCode::Unidentified),
+                logical_key: Key::Character(character.to_string().into()),
+                state: ButtonState::Pressed,
+                text: None,
+                repeat: false,
+                window: Entity::PLACEHOLDER,
+            });
+        }
+        self.app.update();
+        self
+    }
+
+    fn focused(&self) -> Option<Entity> {
+        self.app.world().resource::<InputFocus>().0
+    }
+
+    fn drain<E: Event>(&mut self) -> Vec<E> {
+        self.app.world_mut().resource_mut::<Events<E>>().drain().collect()
+    }
+}
+
+#[test]
+fn first_key_press_focuses_the_top_button() {
+    let mut menu = Menu::with_buttons(3);
+
+    menu.tap(KeyCode::ArrowDown);
+
+    assert_eq!(menu.focused(), Some(menu.widgets[0]));
+}
+
+#[test]
+fn arrow_keys_move_the_focus_and_wrap_around() {
+    let mut menu = Menu::with_buttons(3);
+    menu.tap(KeyCode::ArrowDown);
+
+    menu.tap(KeyCode::ArrowDown).tap(KeyCode::ArrowDown);
+    assert_eq!(menu.focused(), Some(menu.widgets[2]));
+
+    menu.tap(KeyCode::ArrowDown);
+    assert_eq!(menu.focused(), Some(menu.widgets[0]));
+
+    menu.tap(KeyCode::ArrowUp);
+    assert_eq!(menu.focused(), Some(menu.widgets[2]));
+}
+
+#[test]
+fn enter_presses_the_focused_button() {
+    let mut menu = Menu::with_buttons(2);
+    menu.tap(KeyCode::ArrowDown).tap(KeyCode::ArrowDown);
+    menu.drain::<ButtonPressed>();
+
+    menu.tap(KeyCode::Enter);
+
+    assert_eq!(menu.drain::<ButtonPressed>(), [ButtonPressed { entity: menu.widgets[1] }]);
+}
+
+#[test]
+fn slider_snaps_to_its_step() {
+    let mut slider = Slider::new(0.0, 10.0, 0.0).with_step(2.5);
+
+    slider.set_value(0.3);
+    assert_eq!(slider.get_absolute_value(), 2.5);
+
+    slider.step_by(3);
+    assert_eq!(slider.get_absolute_value(), 10.0);
+}
+
+#[test]
+fn arrow_keys_step_a_focused_slider() {
+    let mut menu = Menu::new();
+    let slider = menu.add((Slider::new(0.0, 100.0, 50.0).with_step(10.0), Focusable));
+    menu.tap(KeyCode::ArrowDown);
+
+    let mut values = Vec::new();
+    for key in [KeyCode::ArrowRight, KeyCode::ArrowRight, KeyCode::ArrowLeft] {
+        menu.tap(key);
+        values.extend(menu.drain::<SliderChanged>().iter().map(|event| event.value));
+    }
+
+    assert_eq!(values, [60.0, 70.0, 60.0]);
+    assert_eq!(menu.focused(), Some(slider));
+}
+
+#[test]
+fn toggle_flips_when_pressed() {
+    let mut menu = Menu::new();
+    let toggle = menu.add((Toggle { on: false }, Focusable));
+    menu.tap(KeyCode::ArrowDown);
+
+    menu.tap(KeyCode::Enter);
+    assert_eq!(menu.drain::<ToggleChanged>(), [ToggleChanged { entity: toggle, on: true }]);
+
+    menu.tap(KeyCode::Enter);
+    assert_eq!(menu.drain::<ToggleChanged>(), [ToggleChanged { entity: toggle, on: false }]);
+}
+
+#[test]
+fn cycle_goes_both_ways_and_wraps_around() {
+    let mut menu = Menu::new();
+    let cycle = menu.add((Cycle::new(["Easy", "Normal", "Hard"], 0), Focusable));
+    menu.tap(KeyCode::ArrowDown);
+
+    menu.tap(KeyCode::ArrowLeft);
+    menu.tap(KeyCode::Enter);
+
+    assert_eq!(menu.drain::<CycleChanged>(), [
+        CycleChanged { entity: cycle, index: 2 },
+        CycleChanged { entity: cycle, index: 0 },
+    ]);
+}
+
+#[test]
+fn text_input_keeps_allowed_characters_up_to_its_length() {
+    let mut menu = Menu::new();
+    let input = TextInput::new(3).with_filter(|character| character.is_ascii_alphabetic().then(|| character.to_ascii_uppercase()));
+    let input = menu.add(input);
+    menu.app.update();
+
+    menu.type_text("a-b1cd");
+
+    assert_eq!(menu.drain::<TextChanged>().last(), Some(&TextChanged { entity: input, value: "ABC".to_string() }));
+    assert_eq!(menu.app.world().get::<Text>(input).unwrap().0, "ABC");
+}
+
+#[test]
+fn dragged_slider_follows_the_cursor_outside_its_bar_until_released() {
+    let mut menu = Menu::new();
+    let slider = menu.add(Slider::new(0.0, 100.0, 50.0).with_step(1.0));
+    let cursor = |menu: &mut Menu, x: f32| {
+        menu.app.world_mut().get_mut::<RelativeCursorPosition>(slider).unwrap().normalized = Some(Vec2::new(x, 0.5));
+    };
+    menu.app.update();
+
+    menu.app.world_mut().resource_mut::<ButtonInput<MouseButton>>().press(MouseButton::Left);
+    *menu.app.world_mut().get_mut::<Interaction>(slider).unwrap() = Interaction::Pressed;
+    cursor(&mut menu, 0.2);
+    menu.app.update();
+    // The cursor left the bar, so the slider isn't pressed anymore.
+    *menu.app.world_mut().get_mut::<Interaction>(slider).unwrap() = Interaction::None;
+    cursor(&mut menu, 1.7);
+    menu.app.update();
+    assert_eq!(menu.drain::<SliderChanged>(), [
+        SliderChanged { entity: slider, value: 20.0 },
+        SliderChanged { entity: slider, value: 100.0 },
+    ]);
+
+    menu.app.world_mut().resource_mut::<ButtonInput<MouseButton>>().release(MouseButton::Left);
+    menu.app.update();
+    cursor(&mut menu, 0.4);
+    menu.app.update();
+    assert_eq!(menu.drain::<SliderChanged>(), []);
+}